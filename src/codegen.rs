@@ -1,20 +1,254 @@
-use swc_common::{sync::Lrc, SourceMap};
-use swc_ecma_ast::Module;
-use swc_ecma_codegen::text_writer::JsWriter;
-use swc_ecma_codegen::{Config, Emitter};
+use swc_common::comments::Comments;
+use swc_common::{sync::Lrc, BytePos, SourceMap, Span};
+use swc_ecma_ast::{CallExpr, Callee, Expr, Module, TsInstantiation};
+use swc_ecma_codegen::text_writer::{JsWriter, WriteJs};
+use swc_ecma_codegen::{Config, Emitter, Result};
+use swc_ecma_visit::{VisitMut, VisitMutWith};
+
+use crate::error::OtcError;
+
+// The indentation the emitter writes per level
+const INDENT: &str = "    ";
+
+/**
+ * The emitter drops the type arguments of calls, e.g. `ref<string>('')`, but does emit
+ * instantiation expressions. Move type arguments onto the callee so `f<T>()` is written as is.
+ */
+struct CallTypeArgs;
+impl VisitMut for CallTypeArgs {
+    fn visit_mut_call_expr(&mut self, call_expr: &mut CallExpr) {
+        call_expr.visit_mut_children_with(self);
+
+        if let (Some(type_args), Callee::Expr(callee)) =
+            (call_expr.type_args.take(), &mut call_expr.callee)
+        {
+            **callee = Expr::TsInstantiation(TsInstantiation {
+                span: Default::default(),
+                expr: callee.clone(),
+                type_args,
+            });
+        }
+    }
+}
+
+/**
+ * Wraps a writer so that block comments which were followed by a newline in the
+ * original source are followed by a newline in the output too. The emitter always
+ * writes a space after block comments, which glues JSDoc to the following code.
+ *
+ * The lines of a block comment are also re-indented, since comments moved into
+ * setup end up at a different depth than the one they were written at.
+ */
+struct BlockCommentWriter<W: WriteJs> {
+    inner: W,
+    cm: Lrc<SourceMap>,
+
+    // The last position the emitter mapped, which is the end of a block comment right before `*/`
+    last_pos: Option<BytePos>,
+
+    // The end of the block comment that was just written, if any
+    comment_end: Option<BytePos>,
+
+    // The start of the block comment being written, if any
+    comment_start: Option<BytePos>,
+
+    // The current indentation level, as the inner writer has it
+    indent: usize,
+}
+impl<W: WriteJs> BlockCommentWriter<W> {
+    fn followed_by_newline(&self, pos: BytePos) -> bool {
+        let loc = self.cm.lookup_byte_offset(pos);
+        let rest = match loc.sf.src.get(loc.pos.0 as usize..) {
+            Some(rest) => rest,
+            None => return false,
+        };
+        rest.chars()
+            .find(|c| !(c.is_whitespace() && *c != '\n'))
+            .is_none_or(|c| c == '\n')
+    }
+
+    fn clear(&mut self) {
+        self.comment_end = None;
+    }
+
+    /**
+     * The text of a block comment starting at `start`, with the lines after the first moved from
+     * the column the comment was written at to the current indentation
+     */
+    fn reindent(&self, start: BytePos, text: &str) -> Option<String> {
+        let loc = self.cm.lookup_byte_offset(start);
+        let src = &loc.sf.src;
+        let start = loc.pos.0 as usize;
+        if !text.contains('\n') || !src.get(start..)?.starts_with("/*") {
+            return None;
+        }
+
+        let line_start = src[..start].rfind('\n').map_or(0, |index| index + 1);
+        let column = src[line_start..start]
+            .chars()
+            .take_while(|c| c.is_whitespace())
+            .count();
+        let indent = INDENT.repeat(self.indent);
+
+        let mut lines = text.split('\n');
+        let mut reindented = lines.next().unwrap_or_default().to_string();
+        for line in lines {
+            let whitespace = line
+                .chars()
+                .take_while(|c| c.is_whitespace())
+                .count()
+                .min(column);
+            let rest: String = line.chars().skip(whitespace).collect();
+            reindented.push('\n');
+            reindented.push_str(&indent);
+            reindented.push_str(&rest);
+        }
+
+        Some(reindented)
+    }
+}
+impl<W: WriteJs> WriteJs for BlockCommentWriter<W> {
+    fn increase_indent(&mut self) -> Result {
+        self.clear();
+        self.indent += 1;
+        self.inner.increase_indent()
+    }
+
+    fn decrease_indent(&mut self) -> Result {
+        self.clear();
+        self.indent = self.indent.saturating_sub(1);
+        self.inner.decrease_indent()
+    }
+
+    fn write_semi(&mut self, span: Option<Span>) -> Result {
+        self.clear();
+        self.inner.write_semi(span)
+    }
+
+    fn write_space(&mut self) -> Result {
+        match self.comment_end.take() {
+            Some(end) if self.followed_by_newline(end) => self.inner.write_line(),
+            _ => self.inner.write_space(),
+        }
+    }
+
+    fn write_keyword(&mut self, span: Option<Span>, s: &'static str) -> Result {
+        self.clear();
+        self.inner.write_keyword(span, s)
+    }
+
+    fn write_operator(&mut self, span: Option<Span>, s: &str) -> Result {
+        self.clear();
+        self.inner.write_operator(span, s)
+    }
+
+    fn write_param(&mut self, s: &str) -> Result {
+        self.clear();
+        self.inner.write_param(s)
+    }
+
+    fn write_property(&mut self, s: &str) -> Result {
+        self.clear();
+        self.inner.write_property(s)
+    }
+
+    fn write_line(&mut self) -> Result {
+        self.clear();
+        self.inner.write_line()
+    }
+
+    fn write_lit(&mut self, span: Span, s: &str) -> Result {
+        self.clear();
+        self.inner.write_lit(span, s)
+    }
+
+    fn write_comment(&mut self, s: &str) -> Result {
+        // The emitter maps the start of a block comment before `/*`, and the position two bytes
+        // before its end before `*/`
+        self.comment_end = match (s, self.last_pos) {
+            ("*/", Some(pos)) => Some(pos + BytePos(2)),
+            _ => None,
+        };
+        let start = match s {
+            "/*" => {
+                self.comment_start = self.last_pos;
+                return self.inner.write_comment(s);
+            }
+            _ => self.comment_start.take(),
+        };
+
+        match start.and_then(|start| self.reindent(start, s)) {
+            Some(text) => self.inner.write_comment(&text),
+            None => self.inner.write_comment(s),
+        }
+    }
+
+    fn write_str_lit(&mut self, span: Span, s: &str) -> Result {
+        self.clear();
+        self.inner.write_str_lit(span, s)
+    }
+
+    fn write_str(&mut self, s: &str) -> Result {
+        self.clear();
+        self.inner.write_str(s)
+    }
+
+    fn write_symbol(&mut self, span: Span, s: &str) -> Result {
+        self.clear();
+        self.inner.write_symbol(span, s)
+    }
+
+    fn write_punct(&mut self, span: Option<Span>, s: &'static str) -> Result {
+        self.clear();
+        self.inner.write_punct(span, s)
+    }
+
+    fn care_about_srcmap(&self) -> bool {
+        self.inner.care_about_srcmap()
+    }
+
+    fn add_srcmap(&mut self, pos: BytePos) -> Result {
+        self.last_pos = Some(pos);
+        self.inner.add_srcmap(pos)
+    }
+}
+
+/** Write a module as code, or fail if the emitter can't write some node of it */
+pub fn emit_module(
+    module: &Module,
+    cm: Lrc<SourceMap>,
+    comments: Option<&dyn Comments>,
+) -> std::result::Result<String, OtcError> {
+    let unwritable = |reason: String| {
+        OtcError::Unsupported(format!(
+            "the converted module could not be written: {}",
+            reason
+        ))
+    };
+
+    let mut module = module.clone();
+    module.visit_mut_with(&mut CallTypeArgs);
 
-pub fn emit_module(module: &Module, cm: Lrc<SourceMap>) -> String {
     let mut buf = vec![];
     {
-        let writer = Box::new(JsWriter::new(cm.clone(), "\n", &mut buf, None));
+        let writer = Box::new(BlockCommentWriter {
+            inner: JsWriter::new(cm.clone(), "\n", &mut buf, None),
+            cm: cm.clone(),
+            last_pos: None,
+            comment_end: None,
+            comment_start: None,
+            indent: 0,
+        });
         let mut emitter = Emitter {
             cfg: Config::default(),
-            comments: None,
+            comments,
             cm: cm.clone(),
             wr: writer,
         };
-        emitter.emit_module(&module).unwrap();
+        emitter
+            .emit_module(&module)
+            .map_err(|err| unwritable(err.to_string()))?;
     }
 
-    String::from_utf8(buf).unwrap()
+    String::from_utf8(buf).map_err(|err| unwritable(err.to_string()))
 }
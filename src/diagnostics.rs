@@ -0,0 +1,144 @@
+use std::fmt;
+use std::path::PathBuf;
+
+use serde::Serialize;
+use swc_common::{SourceMap, Span};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Note => write!(f, "note"),
+        }
+    }
+}
+
+/** A 1-based line and column in the original file */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct SourceRange {
+    pub start: Position,
+    pub end: Position,
+}
+
+/** Something noteworthy found while converting a component */
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+
+    // A short, stable identifier for the kind of diagnostic, e.g. `parse-error`
+    pub code: &'static str,
+
+    pub message: String,
+
+    // The file the diagnostic belongs to, if known
+    pub file: Option<PathBuf>,
+
+    // The location in the original file, if known
+    pub range: Option<SourceRange>,
+
+    // The span in the parsed script, resolved into `range` once conversion finishes
+    #[serde(skip)]
+    pub span: Option<Span>,
+}
+impl Diagnostic {
+    pub fn new(severity: Severity, code: &'static str, message: impl Into<String>) -> Diagnostic {
+        Self {
+            severity,
+            code,
+            message: message.into(),
+            file: None,
+            range: None,
+            span: None,
+        }
+    }
+
+    pub fn error(code: &'static str, message: impl Into<String>) -> Diagnostic {
+        Self::new(Severity::Error, code, message)
+    }
+
+    pub fn warning(code: &'static str, message: impl Into<String>) -> Diagnostic {
+        Self::new(Severity::Warning, code, message)
+    }
+
+    pub fn note(code: &'static str, message: impl Into<String>) -> Diagnostic {
+        Self::new(Severity::Note, code, message)
+    }
+
+    pub fn with_span(mut self, span: Span) -> Diagnostic {
+        if !span.is_dummy() {
+            self.span = Some(span);
+        }
+        self
+    }
+
+    /**
+     * Resolve the span of this diagnostic into a line/column range.
+     *
+     * `offset` is the position of the start of the script within the file, which
+     * is not the start of the file for single file components.
+     */
+    pub fn locate(&mut self, cm: &SourceMap, offset: Position) {
+        let span = match self.span {
+            Some(span) if !span.is_dummy() => span,
+            _ => return,
+        };
+
+        let position = |pos| {
+            let loc = cm.lookup_char_pos(pos);
+            Position {
+                line: loc.line + offset.line - 1,
+                column: if loc.line == 1 {
+                    loc.col.0 + offset.column
+                } else {
+                    loc.col.0 + 1
+                },
+            }
+        };
+
+        self.range = Some(SourceRange {
+            start: position(span.lo),
+            end: position(span.hi),
+        });
+    }
+}
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut location: Vec<String> = vec![];
+        if let Some(file) = &self.file {
+            location.push(file.display().to_string());
+        }
+        if let Some(range) = &self.range {
+            location.push(range.start.line.to_string());
+            location.push(range.start.column.to_string());
+        }
+        if !location.is_empty() {
+            write!(f, "{}: ", location.join(":"))?;
+        }
+
+        write!(f, "{}[{}]: {}", self.severity, self.code, self.message)
+    }
+}
+
+/** The 1-based position of a byte offset within `source` */
+pub fn position_of(source: &str, offset: usize) -> Position {
+    let before = &source[..offset];
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+    Position {
+        line: before.matches('\n').count() + 1,
+        column: before[line_start..].chars().count() + 1,
+    }
+}
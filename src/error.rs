@@ -0,0 +1,66 @@
+use std::fmt;
+
+use crate::diagnostics::Diagnostic;
+use crate::parser::InvalidScriptError;
+
+/** The reasons a file or script could not be converted */
+#[derive(Debug, Clone)]
+pub enum OtcError {
+    // The file could not be read from disk
+    Unreadable(String),
+
+    // The file has no script block that can be converted
+    NoScript(InvalidScriptError),
+
+    // The script could not be parsed
+    Parse(Box<Diagnostic>),
+
+    // The component uses a construct that can't be converted
+    Unsupported(String),
+}
+impl fmt::Display for OtcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OtcError::Unreadable(reason) => write!(f, "unable to read file: {}", reason),
+            OtcError::NoScript(err) => write!(f, "{}", err),
+            OtcError::Parse(diagnostic) => match &diagnostic.range {
+                Some(range) => write!(
+                    f,
+                    "unable to parse script: {} at {}:{}",
+                    diagnostic.message, range.start.line, range.start.column
+                ),
+                None => write!(f, "unable to parse script: {}", diagnostic.message),
+            },
+            OtcError::Unsupported(reason) => write!(f, "unsupported construct: {}", reason),
+        }
+    }
+}
+impl std::error::Error for OtcError {}
+impl OtcError {
+    /** A short, stable identifier for the kind of error, matching diagnostic codes */
+    pub fn code(&self) -> &'static str {
+        match self {
+            OtcError::Unreadable(_) => "unreadable",
+            OtcError::NoScript(_) => "no-script",
+            OtcError::Parse(diagnostic) => diagnostic.code,
+            OtcError::Unsupported(_) => "unsupported",
+        }
+    }
+
+    /** Whether the file just has nothing to convert, like a component without a `<script>` */
+    pub fn is_nothing_to_convert(&self) -> bool {
+        matches!(
+            self,
+            OtcError::NoScript(InvalidScriptError::NoScript | InvalidScriptError::ScriptSetupOnly)
+        )
+    }
+}
+
+impl From<InvalidScriptError> for OtcError {
+    fn from(err: InvalidScriptError) -> OtcError {
+        match err {
+            InvalidScriptError::Unreadable(reason) => OtcError::Unreadable(reason),
+            err => OtcError::NoScript(err),
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::WalkBuilder;
+
+/** Options controlling which files are picked up when walking directories */
+#[derive(Clone, Debug)]
+pub struct WalkOptions {
+    // Recurse into directories
+    pub recursive: bool,
+
+    // Only files matching one of these globs are included (all files if empty)
+    pub include: Vec<String>,
+
+    // Files and directories matching one of these globs are excluded
+    pub exclude: Vec<String>,
+
+    // The file extensions to pick up, without the leading `.`
+    pub extensions: Vec<String>,
+}
+impl Default for WalkOptions {
+    fn default() -> WalkOptions {
+        Self {
+            recursive: false,
+            include: vec![],
+            exclude: vec![],
+            extensions: vec!["vue".into()],
+        }
+    }
+}
+
+/** A file to convert */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InputFile {
+    // The path of the file, as found
+    pub path: PathBuf,
+
+    // The path relative to the directory it was found in, or to the current directory if passed
+    // explicitly. Explicit files outside the current directory only keep their file name.
+    pub relative: PathBuf,
+}
+
+/** The files found for a set of input paths */
+#[derive(Debug, Default)]
+pub struct CollectedFiles {
+    // The files to convert, in a stable order
+    pub files: Vec<InputFile>,
+
+    // Directories that were not walked, since recursion is disabled
+    pub skipped_dirs: Vec<PathBuf>,
+}
+impl CollectedFiles {
+    /** The relative paths shared by more than one file, which would be written to the same place */
+    pub fn collisions(&self) -> Vec<&Path> {
+        let mut seen: HashSet<&Path> = HashSet::new();
+        let mut collisions: Vec<&Path> = vec![];
+        for file in &self.files {
+            if !seen.insert(&file.relative) && !collisions.contains(&&*file.relative) {
+                collisions.push(&file.relative);
+            }
+        }
+
+        collisions
+    }
+}
+
+fn build_glob_set(globs: &[String]) -> Result<GlobSet, globset::Error> {
+    let mut builder = GlobSetBuilder::new();
+    for glob in globs {
+        builder.add(Glob::new(glob)?);
+    }
+
+    builder.build()
+}
+
+/** The relative path of a file passed explicitly, see `InputFile::relative` */
+fn explicit_relative(path: &Path) -> PathBuf {
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let relative = match path.strip_prefix(&cwd) {
+        Ok(relative) => relative,
+        Err(_) if path.is_relative() => path,
+        Err(_) => Path::new(""),
+    };
+
+    // `./a/X.vue` is `a/X.vue`, while `../X.vue` is outside the current directory
+    let components: Vec<Component> = relative
+        .components()
+        .filter(|component| *component != Component::CurDir)
+        .collect();
+    if !components.is_empty()
+        && components
+            .iter()
+            .all(|component| matches!(component, Component::Normal(_)))
+    {
+        return components.iter().collect();
+    }
+
+    path.file_name().map(PathBuf::from).unwrap_or_default()
+}
+
+fn has_extension(path: &Path, extensions: &[String]) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| extensions.iter().any(|e| e == ext))
+}
+
+/**
+ * Collect the files to convert from the given paths.
+ *
+ * Files passed explicitly are always included. Directories are walked in file name
+ * order when recursion is enabled, skipping anything ignored by `.gitignore` files,
+ * and filtering by extension and the include/exclude globs relative to the directory.
+ */
+pub fn collect_files(
+    paths: &[PathBuf],
+    options: &WalkOptions,
+) -> Result<CollectedFiles, ignore::Error> {
+    let include = build_glob_set(&options.include).map_err(|e| ignore::Error::Glob {
+        glob: None,
+        err: e.to_string(),
+    })?;
+    let exclude = build_glob_set(&options.exclude).map_err(|e| ignore::Error::Glob {
+        glob: None,
+        err: e.to_string(),
+    })?;
+
+    let mut seen: HashSet<PathBuf> = HashSet::new();
+    let mut collected = CollectedFiles::default();
+    for path in paths {
+        if !path.is_dir() {
+            if seen.insert(path.clone()) {
+                collected.files.push(InputFile {
+                    path: path.clone(),
+                    relative: explicit_relative(path),
+                });
+            }
+            continue;
+        }
+
+        if !options.recursive {
+            collected.skipped_dirs.push(path.clone());
+            continue;
+        }
+
+        let root = path.clone();
+        let exclude_dirs = exclude.clone();
+        let walker = WalkBuilder::new(path)
+            .require_git(false)
+            .sort_by_file_name(|a, b| a.cmp(b))
+            .filter_entry(move |entry| {
+                let relative = entry.path().strip_prefix(&root).unwrap_or(entry.path());
+                !exclude_dirs.is_match(relative)
+            })
+            .build();
+
+        for entry in walker {
+            let entry = entry?;
+            if !entry.file_type().is_some_and(|t| t.is_file()) {
+                continue;
+            }
+
+            let file = entry.path();
+            let relative = file.strip_prefix(path).unwrap_or(file);
+            if !has_extension(file, &options.extensions)
+                || (!include.is_empty() && !include.is_match(relative))
+            {
+                continue;
+            }
+
+            if seen.insert(file.to_path_buf()) {
+                collected.files.push(InputFile {
+                    path: file.to_path_buf(),
+                    relative: relative.to_path_buf(),
+                });
+            }
+        }
+    }
+
+    Ok(collected)
+}
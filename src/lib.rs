@@ -1,20 +1,202 @@
+use std::fs;
+use std::path::Path;
+
+use swc_common::comments::SingleThreadedComments;
 use swc_common::FilePathMapping;
 use swc_common::{sync::Lrc, SourceMap};
 
-#[macro_use]
+use diagnostics::Position;
+
 extern crate swc_common;
 extern crate swc_ecma_parser;
 
 // Modules
 pub mod codegen;
+pub mod diagnostics;
+pub mod error;
+pub mod files;
+pub mod options;
 pub mod parser;
+pub mod report;
+pub mod sfc;
 pub mod visitor;
 
-// TODO: Use Result/Option
-pub fn process(source: String) -> String {
+pub use diagnostics::Diagnostic;
+pub use error::OtcError;
+pub use options::{Options, Target};
+
+/** The result of converting a component */
+#[derive(Debug, Clone)]
+pub struct Conversion {
+    // The converted source, identical to the input if nothing was converted
+    pub output: String,
+
+    // Whether an Options API component was found and converted
+    pub converted: bool,
+
+    // The names of the component options that were converted, e.g. `data` or `watch`
+    pub handled: Vec<&'static str>,
+
+    // Warnings and notes about the conversion, located in the original file
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/**
+ * Convert a script that starts at `offset` within the original file. Returned alongside is the
+ * code for a plain `<script>` block next to `<script setup>`, if it needs one.
+ */
+fn convert_script(
+    source: &str,
+    options: &Options,
+    offset: Position,
+) -> Result<(Conversion, Option<String>), OtcError> {
     let cm = Lrc::new(SourceMap::new(FilePathMapping::empty()));
-    match parser::parse_script_js(source, &cm) {
-        Ok(module) => codegen::emit_module(&visitor::visit_module(module), cm),
-        Err(_) => "".into(),
+    let locate = |mut diagnostic: Diagnostic| {
+        diagnostic.locate(&cm, offset);
+        diagnostic.file = options.filename.clone();
+        diagnostic
+    };
+
+    let comments = SingleThreadedComments::default();
+    let mut diagnostics: Vec<Diagnostic> = vec![];
+    let parse = match options.typescript {
+        true => parser::parse_script_ts,
+        false => parser::parse_script_js,
+    };
+    let module = parse(
+        source.to_string(),
+        options.jsx,
+        &cm,
+        &comments,
+        &mut diagnostics,
+    )
+    .map_err(|err| OtcError::Parse(Box::new(locate(*err.0))))?;
+    let module = visitor::visit_module(module, options, Some(&comments), &mut diagnostics)?;
+    let diagnostics = diagnostics.into_iter().map(locate).collect();
+
+    // Leave the source untouched if there's nothing to convert
+    Ok(match module {
+        Some(converted) => {
+            let plain_script = match &converted.plain_script {
+                Some(plain_script) => Some(codegen::emit_module(
+                    plain_script,
+                    cm.clone(),
+                    Some(&comments),
+                )?),
+                None => None,
+            };
+            let conversion = Conversion {
+                output: codegen::emit_module(&converted.module, cm, Some(&comments))?,
+                converted: true,
+                handled: converted.handled,
+                diagnostics,
+            };
+            (conversion, plain_script)
+        }
+        None => {
+            let conversion = Conversion {
+                output: source.to_string(),
+                converted: false,
+                handled: vec![],
+                diagnostics,
+            };
+            (conversion, None)
+        }
+    })
+}
+
+fn convert_sfc(source: &str, options: &Options) -> Result<Conversion, OtcError> {
+    let sfc = sfc::parse_sfc(source).map_err(|err| {
+        let position = diagnostics::position_of(source, err.offset());
+        let mut diagnostic =
+            Diagnostic::error("parse-error", format!("malformed component: {}", err));
+        diagnostic.range = Some(diagnostics::SourceRange {
+            start: position,
+            end: position,
+        });
+        diagnostic.file = options.filename.clone();
+        OtcError::Parse(Box::new(diagnostic))
+    })?;
+    let script = parser::find_script_block(&sfc)?;
+    if options.script_setup && sfc.script_setup().is_some() {
+        return Err(OtcError::Unsupported(
+            "the component already has a <script setup> block".into(),
+        ));
     }
+
+    // The component already uses the Composition API, the `<script>` next to it only holds options
+    if sfc.script_setup().is_some() {
+        return Ok(Conversion {
+            output: source.to_string(),
+            converted: false,
+            handled: vec![],
+            diagnostics: vec![],
+        });
+    }
+
+    // The script block decides whether it's TypeScript or JSX
+    let mut options = options.clone();
+    options.set_lang(script.lang());
+
+    let offset = diagnostics::position_of(source, script.content.start);
+    let (mut conversion, plain_script) =
+        convert_script(script.content_of(source), &options, offset)?;
+    if conversion.converted {
+        let verbatim =
+            parser::multiline_literal_ranges(&conversion.output, options.typescript, options.jsx);
+        conversion.output =
+            sfc::replace_block_content(source, script, &conversion.output, &verbatim);
+        if options.script_setup {
+            conversion.output = sfc::add_block_attribute(&conversion.output, script, "setup");
+        }
+
+        // What `<script setup>` can't hold goes in a plain `<script>` right before it
+        if let Some(plain_script) = plain_script {
+            let verbatim =
+                parser::multiline_literal_ranges(&plain_script, options.typescript, options.jsx);
+            let block = sfc::copy_block(source, script, &plain_script, &verbatim);
+            conversion.output.insert_str(script.range.start, &block);
+        }
+    } else {
+        conversion.output = source.to_string();
+    }
+
+    Ok(conversion)
+}
+
+/** Convert a component, either a single file component or a plain script depending on the options */
+pub fn convert(source: &str, options: &Options) -> Result<Conversion, OtcError> {
+    if options.script_setup && !options.target.allows_script_setup() {
+        return Err(OtcError::Unsupported(format!(
+            "<script setup> output needs Vue 2.7 or later, the target is Vue {}",
+            options.target
+        )));
+    }
+
+    if options.script_setup && !options.sfc {
+        return Err(OtcError::Unsupported(
+            "<script setup> output is only possible for single file components".into(),
+        ));
+    }
+
+    if options.sfc {
+        return convert_sfc(source, options);
+    }
+
+    convert_script(source, options, Position { line: 1, column: 1 })
+        .map(|(conversion, _)| conversion)
+}
+
+/** Convert a script with the default options, returning an empty string if it can't be converted */
+#[deprecated(note = "use `convert`, which reports errors and diagnostics")]
+pub fn process(source: String) -> String {
+    convert(&source, &Options::default())
+        .map(|conversion| conversion.output)
+        .unwrap_or_default()
+}
+
+/** Read and convert a file, treating `.vue` files as single file components and anything else as a script */
+pub fn convert_file(path: &Path, options: &Options) -> Result<Conversion, OtcError> {
+    let source = fs::read_to_string(path).map_err(|e| OtcError::Unreadable(e.to_string()))?;
+    convert(&source, &options.for_path(path))
 }
@@ -1,42 +1,300 @@
-use clap::Parser;
-use std::{fs, path::PathBuf};
+use clap::{ArgGroup, Parser, ValueEnum};
+use similar::TextDiff;
+use std::{fs, io, path::PathBuf};
 
 // Import Lib
+use otc::files::{collect_files, InputFile, WalkOptions};
+use otc::report::Report;
 use otc::*;
 
-/// Search for a pattern in a file and display the lines that contain it.
+/// Convert Vue components from the Options API to the Composition API.
 #[derive(Parser)]
+#[clap(group(ArgGroup::new("output").args(&["in-place", "out-dir", "stdout"]).conflicts_with_all(&["check", "diff"])))]
 struct Cli {
     /// The paths to convert
     #[clap(name = "PATHS", parse(from_os_str), required = true)]
     paths: Vec<PathBuf>,
 
+    #[clap(short, long, help = "Recurse into directories.")]
+    recursive: bool,
+
     #[clap(
-        short,
         long,
-        help = "Recurse into directories.",
-        default_value_t = false
+        value_name = "GLOB",
+        help = "Only convert files matching this glob, relative to the directory being walked. May be repeated."
     )]
-    recursive: bool,
+    include: Vec<String>,
+
+    #[clap(
+        long,
+        value_name = "GLOB",
+        help = "Skip files and directories matching this glob, relative to the directory being walked. May be repeated."
+    )]
+    exclude: Vec<String>,
+
+    #[clap(
+        long = "ext",
+        value_name = "EXT",
+        use_value_delimiter = true,
+        default_value = "vue",
+        help = "File extensions to pick up when walking directories, e.g. `vue,js,ts`."
+    )]
+    extensions: Vec<String>,
+
+    #[clap(long, help = "Overwrite each file with its converted version.")]
+    in_place: bool,
+
+    #[clap(
+        long,
+        value_name = "DIR",
+        parse(from_os_str),
+        help = "Write converted files to this directory, mirroring the input tree layout."
+    )]
+    out_dir: Option<PathBuf>,
+
+    #[clap(
+        long,
+        help = "Print the converted file to stdout. Only valid for a single file, and the default in that case."
+    )]
+    stdout: bool,
+
+    #[clap(
+        long,
+        help = "Don't write anything, exit with a non-zero status if any file would be converted or fails to convert. The files are listed on stderr."
+    )]
+    check: bool,
+
+    #[clap(
+        long,
+        help = "Don't write anything, print a unified diff of each file against its conversion."
+    )]
+    diff: bool,
+
+    #[clap(
+        long,
+        value_name = "VERSION",
+        default_value = "2.6",
+        possible_values = Target::NAMES,
+        help = "The Vue version to convert for. 2.6 imports from @vue/composition-api, 2.7 and 3 import from vue."
+    )]
+    target: Target,
+
+    #[clap(
+        long,
+        help = "Write a <script setup> block instead of a defineComponent export. Needs --target 2.7 or 3."
+    )]
+    script_setup: bool,
+
+    #[clap(
+        long,
+        requires = "script-setup",
+        help = "Declare props with defineProps<...>() and withDefaults in TypeScript components, rather than a runtime object."
+    )]
+    typed_props: bool,
+
+    #[clap(
+        long,
+        help = "Destructure the setup context in the signature, setup(props, { emit, attrs }), rather than using ctx.emit."
+    )]
+    destructure_context: bool,
+
+    #[clap(
+        long,
+        value_enum,
+        value_name = "FORMAT",
+        conflicts_with_all = &["stdout", "diff"],
+        help = "Print a machine readable report of the results to stdout. Nothing is written unless --in-place or --out-dir is passed."
+    )]
+    report: Option<ReportFormat>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ReportFormat {
+    Json,
+    Sarif,
+}
+
+/// Where converted files are written
+enum Output {
+    InPlace,
+    OutDir(PathBuf),
+    Stdout,
+}
+impl Output {
+    fn write(&self, file: &InputFile, contents: &str) -> io::Result<()> {
+        match self {
+            Output::InPlace => fs::write(&file.path, contents),
+            Output::OutDir(dir) => {
+                let dest = dir.join(&file.relative);
+                if let Some(parent) = dest.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(dest, contents)
+            }
+            Output::Stdout => {
+                print!("{}", contents);
+                Ok(())
+            }
+        }
+    }
 }
 
 fn main() {
     let args = Cli::parse();
-    for path in &args.paths {
-        if path.is_dir() {
-            println!(
-                "Skipping directory {}. Use the -r flag to run on folders.",
-                path.clone().into_os_string().into_string().unwrap()
-            );
+    let walk_options = WalkOptions {
+        recursive: args.recursive,
+        include: args.include,
+        exclude: args.exclude,
+        extensions: args.extensions,
+    };
+
+    let collected = match collect_files(&args.paths, &walk_options) {
+        Ok(collected) => collected,
+        Err(err) => {
+            eprintln!("Unable to collect files: {}", err);
+            std::process::exit(2);
+        }
+    };
+
+    let dry_run = args.check
+        || args.diff
+        || (args.report.is_some() && !args.in_place && args.out_dir.is_none());
+    let output = match (args.in_place, args.out_dir, args.stdout) {
+        (true, _, _) => Output::InPlace,
+        (_, Some(dir), _) => Output::OutDir(dir),
+        (_, _, true) if collected.files.len() > 1 => {
+            eprintln!("--stdout can only be used with a single file");
+            std::process::exit(2);
+        }
+        _ if dry_run || collected.files.len() <= 1 => Output::Stdout,
+        _ => {
+            eprintln!("Multiple files found, pass --in-place or --out-dir to choose where they are written");
+            std::process::exit(2);
+        }
+    };
+
+    if let Output::OutDir(dir) = &output {
+        let collisions = collected.collisions();
+        if !collisions.is_empty() {
+            for relative in collisions {
+                eprintln!(
+                    "More than one file would be written to {}",
+                    dir.join(relative).display()
+                );
+            }
+            std::process::exit(2);
+        }
+    }
+
+    for dir in &collected.skipped_dirs {
+        eprintln!(
+            "Skipping directory {}. Use the -r flag to run on folders.",
+            dir.display()
+        );
+    }
+
+    if args.script_setup && !args.target.allows_script_setup() {
+        eprintln!("--script-setup needs --target 2.7 or 3");
+        std::process::exit(2);
+    }
+
+    let options = Options {
+        target: args.target,
+        script_setup: args.script_setup,
+        typed_props: args.typed_props,
+        destructure_context: args.destructure_context,
+        ..Default::default()
+    };
+    let mut changed: Vec<PathBuf> = vec![];
+    let mut failed: Vec<PathBuf> = vec![];
+    let mut report = Report::default();
+    for file in &collected.files {
+        let path = &file.path;
+        let source = match fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(err) => {
+                let err = OtcError::Unreadable(err.to_string());
+                eprintln!("Skipping {}: {}", path.display(), err);
+                report.push(path, &Err(err));
+                failed.push(path.clone());
+                continue;
+            }
+        };
+
+        let result = convert(&source, &options.for_path(path));
+        report.push(path, &result);
+        let conversion = match result {
+            Ok(conversion) => conversion,
+            Err(err) => {
+                eprintln!("Skipping {}: {}", path.display(), err);
+                if !err.is_nothing_to_convert() {
+                    failed.push(path.clone());
+                }
+                continue;
+            }
+        };
+
+        for diagnostic in &conversion.diagnostics {
+            eprintln!("{}", diagnostic);
+        }
+
+        if dry_run {
+            if conversion.converted {
+                changed.push(path.clone());
+            }
+
+            if args.diff {
+                print_diff(file, &source, &conversion.output);
+            }
             continue;
         }
 
-        let script = parser::parse_vue_script(path);
-        if script.is_err() {
+        // Don't touch files that have nothing to convert
+        if !conversion.converted && matches!(output, Output::InPlace) {
             continue;
         }
 
-        let res = process(script.unwrap());
-        fs::write("output.js", &res).unwrap();
+        if let Err(err) = output.write(file, &conversion.output) {
+            eprintln!("Unable to write {}: {}", path.display(), err);
+        }
+    }
+
+    match args.report {
+        Some(ReportFormat::Json) => println!("{}", report.to_json()),
+        Some(ReportFormat::Sarif) => println!("{}", report.to_sarif()),
+        None => {}
+    }
+
+    // Printed to stderr so it doesn't mix with a report on stdout
+    // Files that can't be converted still need work, so they fail the check too
+    if args.check && !(changed.is_empty() && failed.is_empty()) {
+        if !changed.is_empty() {
+            eprintln!("{} file(s) would be converted:", changed.len());
+            for path in &changed {
+                eprintln!("    {}", path.display());
+            }
+        }
+        if !failed.is_empty() {
+            eprintln!("{} file(s) could not be converted:", failed.len());
+            for path in &failed {
+                eprintln!("    {}", path.display());
+            }
+        }
+        std::process::exit(1);
+    }
+}
+
+/// Print a unified diff between a file and its converted version, if they differ
+fn print_diff(file: &InputFile, original: &str, converted: &str) {
+    if original == converted {
+        return;
     }
+
+    let path = file.path.display().to_string();
+    print!(
+        "{}",
+        TextDiff::from_lines(original, converted)
+            .unified_diff()
+            .header(&format!("a/{}", path), &format!("b/{}", path))
+    );
 }
@@ -0,0 +1,152 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/** How `this.$foo` globals are reached from inside `setup` */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GlobalAccess {
+    // Through the root instance on the setup context, `ctx.root.$foo`
+    ContextRoot,
+
+    // Through the proxy of the current instance, captured at the start of `setup`
+    InstanceProxy,
+}
+
+/** The Vue version the converted component is written for */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Target {
+    // Vue 2.6 with the `@vue/composition-api` plugin
+    #[default]
+    Vue2_6,
+
+    // Vue 2.7, which ships the Composition API itself
+    Vue2_7,
+
+    Vue3,
+}
+impl Target {
+    /** The names accepted when parsing a target */
+    pub const NAMES: [&'static str; 3] = ["2.6", "2.7", "3"];
+
+    /** The module the Composition API is imported from */
+    pub fn import_source(self) -> &'static str {
+        match self {
+            Target::Vue2_6 => "@vue/composition-api",
+            Target::Vue2_7 | Target::Vue3 => "vue",
+        }
+    }
+
+    /** Whether `set` and `del` are available, Vue 3 tracks added and deleted properties on its own */
+    pub fn has_set_delete(self) -> bool {
+        self != Target::Vue3
+    }
+
+    /** Whether components may be written as `<script setup>` */
+    pub fn allows_script_setup(self) -> bool {
+        self != Target::Vue2_6
+    }
+
+    /** Whether `<script setup>` can set component options with `defineOptions`, built in from Vue 3.3 */
+    pub fn has_define_options(self) -> bool {
+        self == Target::Vue3
+    }
+
+    /**
+     * The property of the setup context an instance property is reached through, e.g. `emit` for
+     * `$emit`. Vue 3 has no listeners, they're part of `attrs`.
+     */
+    pub fn context_property(self, name: &str) -> Option<&'static str> {
+        match name {
+            "$emit" => Some("emit"),
+            "$attrs" => Some("attrs"),
+            "$slots" | "$scopedSlots" => Some("slots"),
+            "$listeners" if self == Target::Vue3 => Some("attrs"),
+            "$listeners" => Some("listeners"),
+            _ => None,
+        }
+    }
+
+    /** How globals like `this.$router` are reached once there's no `this` */
+    pub fn global_access(self) -> GlobalAccess {
+        match self {
+            Target::Vue2_6 => GlobalAccess::ContextRoot,
+            Target::Vue2_7 | Target::Vue3 => GlobalAccess::InstanceProxy,
+        }
+    }
+}
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Target::Vue2_6 => "2.6",
+            Target::Vue2_7 => "2.7",
+            Target::Vue3 => "3",
+        })
+    }
+}
+impl FromStr for Target {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim_start_matches("vue") {
+            "2.6" => Ok(Target::Vue2_6),
+            "2.7" => Ok(Target::Vue2_7),
+            "3" => Ok(Target::Vue3),
+            _ => Err(format!(
+                "unknown Vue target `{}`, expected one of {}",
+                s,
+                Target::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+/** Options controlling how a component is converted */
+#[derive(Clone, Debug, Default)]
+pub struct Options {
+    // Treat the source as a single file component, rather than a plain script
+    pub sfc: bool,
+
+    // The file being converted, attached to diagnostics
+    pub filename: Option<PathBuf>,
+
+    // The Vue version to write the component for
+    pub target: Target,
+
+    // Write a `<script setup>` block instead of a `defineComponent` export
+    pub script_setup: bool,
+
+    // Parse the script as TypeScript, set for `.ts` and `.tsx` files and `lang="ts"` or
+    // `lang="tsx"` script blocks
+    pub typescript: bool,
+
+    // Parse JSX in the script, set for `.jsx` and `.tsx` files and `lang="jsx"` or `lang="tsx"`
+    // script blocks
+    pub jsx: bool,
+
+    // Declare props with a type rather than a runtime object, in TypeScript `<script setup>` output
+    pub typed_props: bool,
+
+    // Destructure the setup context in the signature, `setup(props, { emit })`, instead of using `ctx`
+    pub destructure_context: bool,
+}
+impl Options {
+    /**
+     * The options to use for a file, treating `.vue` files as single file components, `.ts`
+     * and `.tsx` files as TypeScript, and `.jsx` and `.tsx` files as JSX
+     */
+    pub fn for_path(&self, path: &Path) -> Options {
+        let mut options = self.clone();
+        options.sfc = path.extension().is_some_and(|ext| ext == "vue");
+        options.filename = Some(path.to_path_buf());
+        if !options.sfc {
+            options.set_lang(path.extension().and_then(|ext| ext.to_str()));
+        }
+        options
+    }
+
+    /** Set how the script is parsed from its language, a file extension or `lang` attribute */
+    pub fn set_lang(&mut self, lang: Option<&str>) {
+        self.typescript = matches!(lang, Some("ts" | "tsx"));
+        self.jsx = matches!(lang, Some("jsx" | "tsx"));
+    }
+}
@@ -1,67 +1,195 @@
+use std::fmt;
 use std::fs;
-use std::path::PathBuf;
-
-use nom::{
-    bytes::complete::{take, take_until},
-    IResult,
-};
-use swc_common::errors::{ColorConfig, Handler};
-use swc_common::{sync::Lrc, FileName, SourceMap};
-use swc_ecma_ast::Module;
-use swc_ecma_parser::{lexer::Lexer, Parser, StringInput, Syntax};
-
-// TODO: Use vue-sfc
+use std::ops::Range;
+use std::path::Path;
+
+use swc_common::comments::SingleThreadedComments;
+use swc_common::{sync::Lrc, FileName, FilePathMapping, SourceMap, Span, Spanned};
+use swc_ecma_ast::{Module, Str, TplElement};
+use swc_ecma_parser::{lexer::Lexer, EsConfig, Parser, StringInput, Syntax, TsConfig};
+use swc_ecma_visit::{Visit, VisitWith};
+
+use crate::diagnostics::Diagnostic;
+use crate::sfc::{parse_sfc, Block, Sfc, SfcError};
+
 #[derive(Debug, Clone)]
-pub struct InvalidScriptError;
-pub fn parse_vue_script(path: &PathBuf) -> Result<String, InvalidScriptError> {
-    let pathstr = path.to_str().unwrap();
-    let data =
-        fs::read_to_string(path).expect(format!("Unable to read file: {}", pathstr).as_str());
+pub enum InvalidScriptError {
+    // The file could not be read from disk
+    Unreadable(String),
 
-    let prescriptres: IResult<&str, &str> = take_until("<script>")(data.as_str());
-    if prescriptres.is_err() {
-        return Err(InvalidScriptError);
+    // The file is not a well formed single file component
+    Malformed(SfcError),
+
+    // The file has no `<script>` block
+    NoScript,
+
+    // The file only has a `<script setup>` block, and is already using the Composition API
+    ScriptSetupOnly,
+
+    // The script is loaded from another file via `<script src="...">`
+    ExternalSource(String),
+}
+impl fmt::Display for InvalidScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvalidScriptError::Unreadable(reason) => write!(f, "unable to read file: {}", reason),
+            InvalidScriptError::Malformed(err) => write!(f, "malformed component: {}", err),
+            InvalidScriptError::NoScript => write!(f, "no <script> block found"),
+            InvalidScriptError::ScriptSetupOnly => {
+                write!(f, "only a <script setup> block found, nothing to convert")
+            }
+            InvalidScriptError::ExternalSource(src) => {
+                write!(f, "script is loaded from external file \"{}\"", src)
+            }
+        }
     }
+}
+impl std::error::Error for InvalidScriptError {}
 
-    // Take <script>
-    let prescript: IResult<&str, &str> = take(8usize)(prescriptres.unwrap().0);
-    let remaining = prescript.unwrap().0;
+/** Find the script block to convert in a single file component */
+pub fn find_script_block(sfc: &Sfc) -> Result<&Block, InvalidScriptError> {
+    let script = match (sfc.script(), sfc.script_setup()) {
+        (Some(script), _) => script,
+        (None, Some(_)) => return Err(InvalidScriptError::ScriptSetupOnly),
+        (None, None) => return Err(InvalidScriptError::NoScript),
+    };
 
-    // Grab remaining data
-    let scriptres: IResult<&str, &str> = take_until("</script>")(remaining);
-    if scriptres.is_err() {
-        return Err(InvalidScriptError);
+    if let Some(src) = script.attr_value("src") {
+        return Err(InvalidScriptError::ExternalSource(src.to_string()));
     }
 
-    return Ok(scriptres.unwrap().1.to_string());
+    Ok(script)
+}
+
+pub fn parse_vue_script(path: &Path) -> Result<String, InvalidScriptError> {
+    let data =
+        fs::read_to_string(path).map_err(|e| InvalidScriptError::Unreadable(e.to_string()))?;
+    let sfc = parse_sfc(&data).map_err(InvalidScriptError::Malformed)?;
+    let script = find_script_block(&sfc)?;
+
+    Ok(script.content_of(&data).to_string())
 }
 
 #[derive(Debug, Clone)]
-pub struct CouldNotParseModule;
-pub fn parse_script_js(source: String, cm: &Lrc<SourceMap>) -> Result<Module, CouldNotParseModule> {
-    let handler = Handler::with_tty_emitter(ColorConfig::Auto, true, false, Some(cm.clone()));
+pub struct CouldNotParseModule(pub Box<Diagnostic>);
+
+fn parse_error_diagnostic(err: swc_ecma_parser::error::Error) -> Diagnostic {
+    Diagnostic::error("parse-error", err.kind().msg()).with_span(err.span())
+}
+
+/**
+ * Parse a script as a module, collecting recoverable parse errors into `diagnostics`,
+ * and comments into `comments` so they can be emitted again
+ */
+fn parse_script(
+    source: String,
+    syntax: Syntax,
+    cm: &Lrc<SourceMap>,
+    comments: &SingleThreadedComments,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<Module, CouldNotParseModule> {
     let fm = cm.new_source_file(FileName::Custom("test.js".into()), source);
     let lexer = Lexer::new(
-        // We want to parse ecmascript
-        Syntax::Es(Default::default()),
+        syntax,
         // EsVersion defaults to es5
         Default::default(),
         StringInput::from(&*fm),
-        None,
+        Some(comments),
     );
 
     let mut parser = Parser::new_from(lexer);
-    for e in parser.take_errors() {
-        e.into_diagnostic(&handler).emit();
-    }
-
-    let parse_res = parser.parse_module().map_err(|e| {
+    let module = parser.parse_module().map_err(|e| {
         // Unrecoverable fatal error occurred
-        e.into_diagnostic(&handler).emit()
+        CouldNotParseModule(Box::new(parse_error_diagnostic(e)))
     });
 
-    match parse_res {
-        Ok(module) => Ok(module),
-        Err(_) => Err(CouldNotParseModule),
+    diagnostics.extend(parser.take_errors().into_iter().map(parse_error_diagnostic));
+    module
+}
+
+/** Parse an ecmascript module, with JSX if `jsx` is set, see `parse_script` */
+pub fn parse_script_js(
+    source: String,
+    jsx: bool,
+    cm: &Lrc<SourceMap>,
+    comments: &SingleThreadedComments,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<Module, CouldNotParseModule> {
+    parse_script(
+        source,
+        Syntax::Es(EsConfig {
+            jsx,
+            ..Default::default()
+        }),
+        cm,
+        comments,
+        diagnostics,
+    )
+}
+
+/**
+ * Parse a TypeScript module, keeping its type annotations, as TSX if `jsx` is set, see
+ * `parse_script`
+ */
+pub fn parse_script_ts(
+    source: String,
+    jsx: bool,
+    cm: &Lrc<SourceMap>,
+    comments: &SingleThreadedComments,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<Module, CouldNotParseModule> {
+    parse_script(
+        source,
+        Syntax::Typescript(TsConfig {
+            tsx: jsx,
+            ..Default::default()
+        }),
+        cm,
+        comments,
+        diagnostics,
+    )
+}
+
+/** Collects the spans of template literal strings and of strings continued over several lines */
+#[derive(Default)]
+struct MultilineLiterals(Vec<Span>);
+
+impl Visit for MultilineLiterals {
+    fn visit_tpl_element(&mut self, element: &TplElement) {
+        self.0.push(element.span);
+    }
+
+    fn visit_str(&mut self, str: &Str) {
+        self.0.push(str.span);
     }
 }
+
+/**
+ * The byte ranges of `code` within template literals and strings, where a line break is part
+ * of the value, so the lines after it can't be re-indented. Empty if `code` can't be parsed.
+ */
+pub fn multiline_literal_ranges(code: &str, typescript: bool, jsx: bool) -> Vec<Range<usize>> {
+    let cm: Lrc<SourceMap> = Lrc::new(SourceMap::new(FilePathMapping::empty()));
+    let comments = SingleThreadedComments::default();
+    let parse = match typescript {
+        true => parse_script_ts,
+        false => parse_script_js,
+    };
+    let module = match parse(code.to_string(), jsx, &cm, &comments, &mut vec![]) {
+        Ok(module) => module,
+        Err(_) => return vec![],
+    };
+
+    let mut literals = MultilineLiterals::default();
+    module.visit_with(&mut literals);
+    literals
+        .0
+        .into_iter()
+        .filter(|span| !span.is_dummy())
+        .map(|span| {
+            cm.lookup_byte_offset(span.lo).pos.0 as usize
+                ..cm.lookup_byte_offset(span.hi).pos.0 as usize
+        })
+        .filter(|range| code[range.clone()].contains('\n'))
+        .collect()
+}
@@ -0,0 +1,189 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::{Conversion, OtcError};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileStatus {
+    // An Options API component was found and converted
+    Converted,
+
+    // There was nothing to convert
+    Skipped,
+
+    // The file could not be converted
+    Failed,
+}
+
+/** Why a file could not be converted */
+#[derive(Clone, Debug, Serialize)]
+pub struct FileError {
+    pub code: &'static str,
+    pub message: String,
+}
+
+/** The outcome of converting a single file */
+#[derive(Clone, Debug, Serialize)]
+pub struct FileReport {
+    pub path: PathBuf,
+    pub status: FileStatus,
+
+    // The component options that were converted, e.g. `data` or `watch`
+    pub handled: Vec<&'static str>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<FileError>,
+
+    pub diagnostics: Vec<Diagnostic>,
+}
+impl FileReport {
+    pub fn new(path: &Path, result: &Result<Conversion, OtcError>) -> FileReport {
+        match result {
+            Ok(conversion) => Self {
+                path: path.to_path_buf(),
+                status: match conversion.converted {
+                    true => FileStatus::Converted,
+                    false => FileStatus::Skipped,
+                },
+                handled: conversion.handled.clone(),
+                error: None,
+                diagnostics: conversion.diagnostics.clone(),
+            },
+            // A component without a `<script>` is fine, there's just nothing to convert
+            Err(err) if err.is_nothing_to_convert() => Self {
+                path: path.to_path_buf(),
+                status: FileStatus::Skipped,
+                handled: vec![],
+                error: None,
+                diagnostics: vec![],
+            },
+            Err(err) => Self {
+                path: path.to_path_buf(),
+                status: FileStatus::Failed,
+                handled: vec![],
+                error: Some(FileError {
+                    code: err.code(),
+                    message: err.to_string(),
+                }),
+                diagnostics: match err {
+                    OtcError::Parse(diagnostic) => vec![*diagnostic.clone()],
+                    _ => vec![],
+                },
+            },
+        }
+    }
+}
+
+/** The outcome of converting a set of files */
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Report {
+    pub files: Vec<FileReport>,
+}
+impl Report {
+    pub fn push(&mut self, path: &Path, result: &Result<Conversion, OtcError>) {
+        self.files.push(FileReport::new(path, result));
+    }
+
+    pub fn count(&self, status: FileStatus) -> usize {
+        self.files.iter().filter(|f| f.status == status).count()
+    }
+
+    /** Per file results, along with a summary of how many files ended up in each status */
+    pub fn to_json(&self) -> String {
+        let report = json!({
+            "summary": {
+                "converted": self.count(FileStatus::Converted),
+                "skipped": self.count(FileStatus::Skipped),
+                "failed": self.count(FileStatus::Failed),
+            },
+            "files": self.files,
+        });
+
+        serde_json::to_string_pretty(&report).unwrap()
+    }
+
+    /** Diagnostics and failures as a SARIF 2.1.0 log, for code scanning tools */
+    pub fn to_sarif(&self) -> String {
+        let mut rules: BTreeSet<&'static str> = BTreeSet::new();
+        let mut results: Vec<Value> = vec![];
+        for file in &self.files {
+            // Failures that don't come with a diagnostic of their own
+            if let (Some(error), true) = (&file.error, file.diagnostics.is_empty()) {
+                rules.insert(error.code);
+                results.push(sarif_result(
+                    error.code,
+                    Severity::Error,
+                    &error.message,
+                    &file.path,
+                    None,
+                ));
+            }
+
+            for diagnostic in &file.diagnostics {
+                rules.insert(diagnostic.code);
+                results.push(sarif_result(
+                    diagnostic.code,
+                    diagnostic.severity,
+                    &diagnostic.message,
+                    &file.path,
+                    Some(diagnostic),
+                ));
+            }
+        }
+
+        let log = json!({
+            "version": "2.1.0",
+            "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": env!("CARGO_PKG_NAME"),
+                        "version": env!("CARGO_PKG_VERSION"),
+                        "rules": rules.iter().map(|id| json!({ "id": id })).collect::<Vec<Value>>(),
+                    },
+                },
+                "results": results,
+            }],
+        });
+
+        serde_json::to_string_pretty(&log).unwrap()
+    }
+}
+
+fn sarif_result(
+    code: &str,
+    severity: Severity,
+    message: &str,
+    path: &Path,
+    diagnostic: Option<&Diagnostic>,
+) -> Value {
+    let mut location = json!({
+        "artifactLocation": {
+            "uri": path.to_string_lossy().replace('\\', "/"),
+        },
+    });
+    if let Some(range) = diagnostic.and_then(|d| d.range) {
+        location["region"] = json!({
+            "startLine": range.start.line,
+            "startColumn": range.start.column,
+            "endLine": range.end.line,
+            "endColumn": range.end.column,
+        });
+    }
+
+    json!({
+        "ruleId": code,
+        "level": match severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "note",
+        },
+        "message": { "text": message },
+        "locations": [{ "physicalLocation": location }],
+    })
+}
@@ -0,0 +1,383 @@
+use std::fmt;
+use std::ops::Range;
+
+use nom::{
+    branch::alt,
+    bytes::complete::{tag, take_till, take_while, take_while1},
+    character::complete::{char, multispace0, multispace1},
+    combinator::{map, opt},
+    multi::many0,
+    sequence::{delimited, preceded, tuple},
+    IResult,
+};
+
+/** A single attribute on a top level block, e.g. `lang="ts"` or `setup` */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Attribute {
+    pub name: String,
+    pub value: Option<String>,
+}
+
+/** A top level block of a single file component (template, script, style, or custom) */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Block {
+    // The tag name, e.g. `script` or `i18n`
+    pub name: String,
+
+    // The attributes of the opening tag, in source order
+    pub attrs: Vec<Attribute>,
+
+    // Byte range of the entire block, from `<` of the opening tag to `>` of the closing tag
+    pub range: Range<usize>,
+
+    // Byte range of the content between the opening and closing tags
+    pub content: Range<usize>,
+}
+impl Block {
+    pub fn attr(&self, name: &str) -> Option<&Attribute> {
+        self.attrs.iter().find(|attr| attr.name == name)
+    }
+
+    pub fn attr_value(&self, name: &str) -> Option<&str> {
+        self.attr(name).and_then(|attr| attr.value.as_deref())
+    }
+
+    pub fn lang(&self) -> Option<&str> {
+        self.attr_value("lang")
+    }
+
+    pub fn is_setup(&self) -> bool {
+        self.attr("setup").is_some()
+    }
+
+    pub fn content_of<'a>(&self, source: &'a str) -> &'a str {
+        &source[self.content.clone()]
+    }
+}
+
+/** All top level blocks of a single file component, in source order */
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Sfc {
+    pub blocks: Vec<Block>,
+}
+impl Sfc {
+    pub fn template(&self) -> Option<&Block> {
+        self.blocks.iter().find(|block| block.name == "template")
+    }
+
+    /** The regular (non setup) script block */
+    pub fn script(&self) -> Option<&Block> {
+        self.blocks
+            .iter()
+            .find(|block| block.name == "script" && !block.is_setup())
+    }
+
+    pub fn script_setup(&self) -> Option<&Block> {
+        self.blocks
+            .iter()
+            .find(|block| block.name == "script" && block.is_setup())
+    }
+
+    pub fn styles(&self) -> impl Iterator<Item = &Block> {
+        self.blocks.iter().filter(|block| block.name == "style")
+    }
+
+    pub fn custom_blocks(&self) -> impl Iterator<Item = &Block> {
+        self.blocks
+            .iter()
+            .filter(|block| !matches!(block.name.as_str(), "template" | "script" | "style"))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SfcError {
+    // An opening tag that could not be parsed, at the given byte offset
+    MalformedTag(usize),
+
+    // A block that was opened at the given byte offset, but never closed
+    UnclosedBlock(String, usize),
+
+    // A comment that was opened at the given byte offset, but never closed
+    UnclosedComment(usize),
+
+    // A closing tag with no matching opening tag, at the given byte offset
+    UnexpectedClosingTag(String, usize),
+}
+impl fmt::Display for SfcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SfcError::MalformedTag(offset) => write!(f, "malformed tag at byte {}", offset),
+            SfcError::UnclosedBlock(name, offset) => {
+                write!(f, "<{}> opened at byte {} is never closed", name, offset)
+            }
+            SfcError::UnclosedComment(offset) => {
+                write!(f, "comment opened at byte {} is never closed", offset)
+            }
+            SfcError::UnexpectedClosingTag(name, offset) => {
+                write!(f, "unexpected </{}> at byte {}", name, offset)
+            }
+        }
+    }
+}
+impl std::error::Error for SfcError {}
+impl SfcError {
+    /** The byte offset the error was found at */
+    pub fn offset(&self) -> usize {
+        match self {
+            SfcError::MalformedTag(offset)
+            | SfcError::UnclosedBlock(_, offset)
+            | SfcError::UnclosedComment(offset)
+            | SfcError::UnexpectedClosingTag(_, offset) => *offset,
+        }
+    }
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | ':' | '.')
+}
+
+fn tag_name(input: &str) -> IResult<&str, &str> {
+    take_while1(is_name_char)(input)
+}
+
+fn attribute_value(input: &str) -> IResult<&str, &str> {
+    alt((
+        delimited(char('"'), take_till(|c| c == '"'), char('"')),
+        delimited(char('\''), take_till(|c| c == '\''), char('\'')),
+        take_while1(|c: char| !c.is_whitespace() && c != '>'),
+    ))(input)
+}
+
+fn attribute(input: &str) -> IResult<&str, Attribute> {
+    map(
+        tuple((
+            take_while1(|c: char| !c.is_whitespace() && !matches!(c, '=' | '/' | '>')),
+            opt(preceded(
+                tuple((multispace0, char('='), multispace0)),
+                attribute_value,
+            )),
+        )),
+        |(name, value): (&str, Option<&str>)| Attribute {
+            name: name.to_string(),
+            value: value.map(|v| v.to_string()),
+        },
+    )(input)
+}
+
+/** Parses an opening tag, returning the name, attributes and whether it is self closing */
+fn opening_tag(input: &str) -> IResult<&str, (&str, Vec<Attribute>, bool)> {
+    map(
+        tuple((
+            char('<'),
+            tag_name,
+            many0(preceded(multispace1, attribute)),
+            multispace0,
+            alt((tag("/>"), tag(">"))),
+        )),
+        |(_, name, attrs, _, end)| (name, attrs, end == "/>"),
+    )(input)
+}
+
+/** Parses a closing tag for `name`, e.g. `</script >` */
+fn closing_tag<'a>(name: &'a str) -> impl FnMut(&'a str) -> IResult<&'a str, &'a str> {
+    move |input| delimited(tag("</"), tag(name), tuple((multispace0, char('>'))))(input)
+}
+
+/** Whether an opening tag named `name` starts at the beginning of `input` */
+fn starts_opening_tag(input: &str, name: &str) -> bool {
+    input
+        .strip_prefix('<')
+        .and_then(|rest| rest.strip_prefix(name))
+        .and_then(|rest| rest.chars().next())
+        .is_some_and(|c| c.is_whitespace() || c == '>' || c == '/')
+}
+
+/** Find the end of a comment starting at `start`, returning the offset after `-->` */
+fn skip_comment(source: &str, start: usize) -> Result<usize, SfcError> {
+    match source[start + 4..].find("-->") {
+        Some(index) => Ok(start + 4 + index + 3),
+        None => Err(SfcError::UnclosedComment(start)),
+    }
+}
+
+/**
+ * Find the closing tag of a block whose content starts at `start`. Returns the
+ * offset of the closing tag and the offset after it.
+ *
+ * Templates may contain nested `<template>` tags, so they are tracked by depth.
+ * Every other block is raw text, and ends at the first matching closing tag.
+ */
+fn find_block_end(source: &str, name: &str, start: usize) -> Option<(usize, usize)> {
+    let nested = name == "template";
+    let mut depth = 0usize;
+    let mut pos = start;
+    while let Some(index) = source[pos..].find('<') {
+        pos += index;
+        let rest = &source[pos..];
+
+        if nested && rest.starts_with("<!--") {
+            pos = skip_comment(source, pos).ok()?;
+            continue;
+        }
+
+        if let Ok((remaining, _)) = closing_tag(name)(rest) {
+            if depth == 0 {
+                return Some((pos, source.len() - remaining.len()));
+            }
+            depth -= 1;
+        } else if nested && starts_opening_tag(rest, name) {
+            if let Ok((_, (_, _, false))) = opening_tag(rest) {
+                depth += 1;
+            }
+        }
+
+        pos += 1;
+    }
+
+    None
+}
+
+/** Parse all top level blocks of a single file component */
+pub fn parse_sfc(source: &str) -> Result<Sfc, SfcError> {
+    let mut blocks: Vec<Block> = vec![];
+    let mut pos = 0;
+    while let Some(index) = source[pos..].find('<') {
+        pos += index;
+        let rest = &source[pos..];
+
+        // Skip comments
+        if rest.starts_with("<!--") {
+            pos = skip_comment(source, pos)?;
+            continue;
+        }
+
+        // Skip doctype and processing instructions
+        if rest.starts_with("<!") || rest.starts_with("<?") {
+            pos += rest.find('>').ok_or(SfcError::MalformedTag(pos))? + 1;
+            continue;
+        }
+
+        // Any closing tag at the top level is unmatched
+        if let Some(closing) = rest.strip_prefix("</") {
+            let (_, name) = take_while::<_, &str, ()>(is_name_char)(closing)
+                .map_err(|_| SfcError::MalformedTag(pos))?;
+            return Err(SfcError::UnexpectedClosingTag(name.to_string(), pos));
+        }
+
+        let (remaining, (name, attrs, self_closing)) =
+            opening_tag(rest).map_err(|_| SfcError::MalformedTag(pos))?;
+        let content_start = source.len() - remaining.len();
+
+        // Self closing blocks have no content, e.g. `<script src="./foo.js" />`
+        let (content, end) = if self_closing {
+            (content_start..content_start, content_start)
+        } else {
+            let (content_end, end) = find_block_end(source, name, content_start)
+                .ok_or_else(|| SfcError::UnclosedBlock(name.to_string(), pos))?;
+            (content_start..content_end, end)
+        };
+
+        blocks.push(Block {
+            name: name.to_string(),
+            attrs,
+            range: pos..end,
+            content,
+        });
+        pos = end;
+    }
+
+    Ok(Sfc { blocks })
+}
+
+/**
+ * Replace the content of `block` within `source`, leaving every other byte untouched.
+ *
+ * The new content is re-indented to match the first line of the original content,
+ * uses the original line endings, and keeps the original leading and trailing whitespace.
+ * Lines that start within one of the `verbatim` byte ranges of `content`, like the lines of
+ * a template literal, are part of a value and are left as they are.
+ */
+pub fn replace_block_content(
+    source: &str,
+    block: &Block,
+    content: &str,
+    verbatim: &[Range<usize>],
+) -> String {
+    let original = block.content_of(source);
+    let line_ending = if original.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+
+    // Whitespace surrounding the original content, e.g. the newlines after `<script>`
+    let trimmed_start = original.trim_start();
+    let leading_whitespace = &original[..original.len() - trimmed_start.len()];
+    let (leading, indent) = match leading_whitespace.rfind('\n') {
+        Some(index) => leading_whitespace.split_at(index + 1),
+        None => ("", leading_whitespace),
+    };
+    let trailing = &original[original.trim_end().len()..];
+
+    let trimmed = content.trim();
+    let mut offset = content.len() - content.trim_start().len();
+    let body = trimmed
+        .split('\n')
+        .map(|line| {
+            let start = offset;
+            offset += line.len() + 1;
+            let line = line.trim_end_matches('\r');
+            let in_value = verbatim
+                .iter()
+                .any(|range| range.start < start && start < range.end);
+            if line.is_empty() || in_value {
+                line.to_string()
+            } else {
+                format!("{}{}", indent, line)
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(line_ending);
+
+    let mut output = String::with_capacity(source.len() + content.len());
+    output.push_str(&source[..block.content.start]);
+    output.push_str(leading);
+    output.push_str(&body);
+    output.push_str(trailing);
+    output.push_str(&source[block.content.end..]);
+    output
+}
+
+/**
+ * A copy of `block` with `content` in place of its content, as `replace_block_content` writes it,
+ * followed by a blank line so that it can be inserted right before `block`.
+ */
+pub fn copy_block(source: &str, block: &Block, content: &str, verbatim: &[Range<usize>]) -> String {
+    let block_source = &source[block.range.clone()];
+    let start = block.range.start;
+    let shifted = Block {
+        range: 0..block_source.len(),
+        content: block.content.start - start..block.content.end - start,
+        ..block.clone()
+    };
+    let line_ending = if block_source.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let copy = replace_block_content(block_source, &shifted, content, verbatim);
+    format!("{}{}{}", copy, line_ending, line_ending)
+}
+
+/**
+ * Add a boolean attribute, e.g. `setup`, to the opening tag of `block`, right after the tag name.
+ * Only the bytes before the block's content are touched, so `block` stays valid for its content.
+ */
+pub fn add_block_attribute(source: &str, block: &Block, name: &str) -> String {
+    if block.attr(name).is_some() {
+        return source.to_string();
+    }
+
+    let index = block.range.start + 1 + block.name.len();
+    format!("{} {}{}", &source[..index], name, &source[index..])
+}
@@ -1,13 +1,17 @@
-use std::{
-    collections::{HashMap, HashSet},
-    iter::FromIterator,
-};
+use std::collections::HashMap;
 
-use string_cache::Atom;
+use swc_common::comments::SingleThreadedComments;
+use swc_common::{Span, Spanned};
 use swc_ecma_ast::*;
-use swc_ecma_visit::{as_folder, FoldWith, Visit, VisitMut, VisitMutWith, VisitWith};
+use swc_ecma_visit::{Visit, VisitMut, VisitMutWith, VisitWith};
 
-use self::{utils::Ordered, vue::Inject};
+use self::{
+    utils::Ordered,
+    vue::{Alias, Binding, Inject},
+};
+use crate::diagnostics::Diagnostic;
+use crate::error::OtcError;
+use crate::options::{GlobalAccess, Options, Target};
 
 // Modules
 pub mod process;
@@ -16,9 +20,7 @@ pub mod utils;
 pub mod vue;
 pub mod write;
 
-const SPECIAL_FUNCTIONS: [&str; 1] = ["$emit"];
-
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct Visitor {
     options: vue::OptionsComponent,
     composition: vue::CompositionComponent,
@@ -26,161 +28,834 @@ pub struct Visitor {
     // and skip that file if so
     // valid: bool,
 
-    // Track props
-    props_set: Option<HashSet<String>>,
+    // Everything reachable through `this`
+    bindings: vue::Bindings,
 
     // Track injects, preserving definition order
     inject_set: Option<HashMap<String, Ordered<Inject>>>,
-    special_functions: HashSet<String>,
-}
-impl Default for Visitor {
-    fn default() -> Visitor {
-        Self {
-            options: Default::default(),
-            composition: Default::default(),
-            // valid: true,
-            props_set: Default::default(),
-            inject_set: Default::default(),
-            special_functions: HashSet::from_iter(SPECIAL_FUNCTIONS.map(|s| s.to_string())),
-        }
-    }
+
+    // Whether an Options API component was found and converted
+    found_component: bool,
+
+    // The error that stopped the conversion, if any
+    error: Option<OtcError>,
+
+    // Warnings gathered during conversion
+    diagnostics: Vec<Diagnostic>,
+
+    // The comments of the module, if they're being preserved
+    comments: Option<SingleThreadedComments>,
+
+    // The keys of the component options, used to carry their comments over
+    option_keys: Vec<(String, Span)>,
+
+    // The Vue version being converted to
+    target: Target,
+
+    // Whether a `<script setup>` block is written instead of a `defineComponent` export
+    script_setup: bool,
+
+    // The items for a plain `<script>` block next to `<script setup>`, see `write_plain_script`
+    plain_script: Vec<ModuleItem>,
+
+    // Whether props are declared with a type, only possible in TypeScript script setup
+    typed_props: bool,
+
+    // Whether `this` is lexically bound to the component instance where the visitor currently is
+    this_is_component: bool,
+
+    // The keys of `this.$refs` used in the code, and the names their refs are declared as
+    template_refs: Vec<(String, Ident)>,
+
+    // The local names standing for `this` or one of its members in the current option function
+    aliases: HashMap<String, Alias>,
+
+    // The names declared by each scope the visitor is in, within the current option function
+    scopes: Vec<Vec<String>>,
+
+    // The `this.$emit` calls whose event name isn't a string literal
+    dynamic_emits: Vec<Span>,
 }
 
 // This is used for analysis before modification
 impl Visit for Visitor {
     fn visit_module_decl(&mut self, decl: &ModuleDecl) {
         if let Some(expr) = decl.as_export_default_expr() {
-            if let Some(obj) = expr.expr.as_object() {
+            if let Some(obj) = utils::component_object(&expr.expr) {
                 self.preprocess_default_export(obj);
             }
         }
     }
 }
 
-// This is used for the AST modification
-impl VisitMut for Visitor {
-    // Since functions aren't defined as refs, they must be handled here first
-    fn visit_mut_call_expr(&mut self, call_expr: &mut CallExpr) {
-        if let Callee::Expr(e) = &mut call_expr.callee {
-            if let Expr::Member(member_expr) = &mut **e {
-                if let (Expr::This(_), MemberProp::Ident(id)) =
-                    (&*member_expr.obj, &member_expr.prop)
+impl Visitor {
+    /** The expression that replaces `this.name`, checking that it isn't shadowed where it's used */
+    fn rewrite_this_member(&mut self, name: Ident, span: Span) -> Expr {
+        let original = format!("this.{}", name.sym);
+        let expr = self.rewrite_binding(name, span);
+        self.check_shadowed(&original, &expr, span);
+        expr
+    }
+
+    /** The expression that replaces `this.name`, according to what `name` is */
+    fn rewrite_binding(&mut self, name: Ident, span: Span) -> Expr {
+        let binding = self.bindings.get(&name.sym).unwrap_or_else(|| {
+            self.diagnostics.push(
+                Diagnostic::warning(
+                    "unknown-member",
+                    format!(
+                        "`this.{}` is not declared by the component, it was converted as a ref",
+                        name.sym
+                    ),
+                )
+                .with_span(span),
+            );
+            Binding::Data
+        });
+
+        match binding {
+            // this.x -> props.x
+            Binding::Prop => utils::member_expr(utils::ident("props"), name),
+            // this.x -> x.value
+            Binding::Data | Binding::Computed => {
+                utils::member_expr(Expr::Ident(name), utils::ident("value"))
+            }
+            // this.x -> x
+            Binding::Method | Binding::Inject => Expr::Ident(name),
+            // this.$emit -> ctx.emit
+            Binding::Context => {
+                let property = self.target.context_property(&name.sym).unwrap();
+
+                // Only `$listeners.click` can be rewritten, as `attrs.onClick`
+                if &*name.sym == "$listeners" && property == "attrs" {
+                    self.diagnostics.push(
+                        Diagnostic::warning(
+                            "listeners-in-attrs",
+                            "Vue 3 has no `$listeners`, this reads `attrs` instead, where listeners are the `onX` properties, so the code needs fixing",
+                        )
+                        .with_span(span),
+                    );
+                }
+                let note = match &*name.sym {
+                    "$slots" if self.target != Target::Vue3 => Some((
+                        "slots-are-functions",
+                        "the slots of the setup context are functions returning VNodes, not VNode arrays like `$slots`",
+                    )),
+                    _ => None,
+                };
+                if let Some((code, message)) = note {
+                    self.diagnostics
+                        .push(Diagnostic::note(code, message).with_span(span));
+                }
+                self.context_property(property)
+            }
+            // this.$nextTick -> nextTick
+            Binding::InstanceProperty if &*name.sym == "$nextTick" => {
+                self.composition.use_api("nextTick");
+                Expr::Ident(utils::ident("nextTick"))
+            }
+            // this.$set -> set, this.$delete -> del
+            Binding::InstanceProperty
+                if matches!(&*name.sym, "$set" | "$delete") && self.target.has_set_delete() =>
+            {
+                let api = if &*name.sym == "$set" { "set" } else { "del" };
+                self.composition.use_api(api);
+                Expr::Ident(utils::ident(api))
+            }
+            // this.$el -> instance.$el
+            Binding::InstanceProperty | Binding::TemplateRef => {
+                let note = match &*name.sym {
+                    "$forceUpdate" => Some((
+                        "force-update",
+                        "`$forceUpdate` is called on the instance from `getCurrentInstance()`, consider making the state it depends on reactive instead",
+                    )),
+                    "$set" | "$delete" => Some((
+                        "no-set-delete",
+                        "Vue 3 has no `set` or `del`, assign or delete the property directly",
+                    )),
+                    _ => None,
+                };
+                if let Some((code, message)) = note {
+                    self.diagnostics
+                        .push(Diagnostic::warning(code, message).with_span(span));
+                }
+
+                self.composition.uses_instance = true;
+                utils::member_expr(utils::ident(write::INSTANCE_IDENT), name)
+            }
+            Binding::Global => match self.target.global_access() {
+                // this.$foo -> ctx.root.$foo
+                GlobalAccess::ContextRoot => {
+                    utils::member_expr(self.context_property("root"), name)
+                }
+                // this.$foo -> instance.$foo
+                GlobalAccess::InstanceProxy => {
+                    self.composition.uses_instance = true;
+                    utils::member_expr(utils::ident(write::INSTANCE_IDENT), name)
+                }
+            },
+        }
+    }
+}
+
+impl Visitor {
+    /**
+     * `this.$set(obj, key, value)` as `obj[key] = value` and `this.$delete(obj, key)` as
+     * `delete obj[key]`, for Vue 3 which tracks added and deleted properties on its own. Returns
+     * `None` for anything else, including calls with spread arguments.
+     */
+    fn rewrite_set_delete(&mut self, call_expr: &mut CallExpr) -> Option<Expr> {
+        if self.target.has_set_delete() {
+            return None;
+        }
+
+        let name = self.component_member_name(call_expr.callee.as_expr()?)?;
+        let arity = match &*name.sym {
+            "$set" => 3,
+            "$delete" => 2,
+            _ => return None,
+        };
+        if call_expr.args.len() != arity || call_expr.args.iter().any(|arg| arg.spread.is_some()) {
+            return None;
+        }
+
+        call_expr.args.visit_mut_with(self);
+        let mut args = call_expr.args.drain(..).map(|arg| arg.expr);
+        let (obj, key) = (args.next()?, args.next()?);
+        let prop = match &*key {
+            Expr::Lit(Lit::Str(key)) if Ident::verify_symbol(&key.value).is_ok() => {
+                MemberProp::Ident(utils::ident(&key.value))
+            }
+            _ => MemberProp::Computed(ComputedPropName {
+                span: key.span(),
+                expr: key,
+            }),
+        };
+        let target = Expr::Member(MemberExpr {
+            span: Default::default(),
+            obj,
+            prop,
+        });
+
+        Some(match args.next() {
+            // this.$set(obj, key, value) -> obj[key] = value
+            Some(value) => Expr::Assign(AssignExpr {
+                span: call_expr.span,
+                op: AssignOp::Assign,
+                left: PatOrExpr::Expr(Box::new(target)),
+                right: value,
+            }),
+            // this.$delete(obj, key) -> delete obj[key]
+            None => Expr::Unary(UnaryExpr {
+                span: call_expr.span,
+                op: UnaryOp::Delete,
+                arg: Box::new(target),
+            }),
+        })
+    }
+
+    /**
+     * The ref declared for the template ref `key`. It's named after the key unless that would
+     * collide with a member of the component or a local in scope, in which case the template has
+     * to be updated.
+     */
+    fn template_ref(&mut self, key: &Ident) -> Ident {
+        if let Some((_, name)) = self.template_refs.iter().find(|(k, _)| *k == *key.sym) {
+            return name.clone();
+        }
+
+        let taken = |visitor: &Self, name: &str| {
+            visitor.bindings.get(name).is_some()
+                || visitor.is_shadowed(name)
+                || visitor
+                    .template_refs
+                    .iter()
+                    .any(|(_, taken)| &*taken.sym == name)
+        };
+        let mut name = key.sym.to_string();
+        if taken(self, &name) {
+            while taken(self, &name) {
+                name.push_str("Ref");
+            }
+            self.diagnostics.push(
+                Diagnostic::warning(
+                    "template-ref-renamed",
+                    format!(
+                        "`$refs.{}` collides with another `{}`, it was declared as `{}`, so the template needs `ref=\"{}\"`",
+                        key.sym, key.sym, name, name
+                    ),
+                )
+                .with_span(key.span),
+            );
+        }
+
+        let name = utils::ident(&name);
+        self.template_refs.push((key.sym.to_string(), name.clone()));
+        name
+    }
+
+    /**
+     * A property of the setup context, `ctx.emit`, or just `emit` if the context is destructured.
+     * In script setup there's no context, and the property is declared on its own.
+     */
+    fn context_property(&mut self, property: &'static str) -> Expr {
+        self.composition.use_context(property);
+        if self.script_setup && property == "emit" {
+            self.composition.calls_emit = true;
+        }
+
+        if self.script_setup || self.composition.destructure_context {
+            Expr::Ident(utils::ident(property))
+        } else {
+            utils::member_expr(utils::ident("ctx"), utils::ident(property))
+        }
+    }
+}
+
+impl Visitor {
+    /**
+     * Visit the converted options of the component object, where `this` is the component instance
+     * only inside option functions and the arrow functions nested in them. The options that are
+     * passed through still run with `this`, so they're left alone.
+     */
+    fn visit_mut_component(&mut self, obj: &mut ObjectLit) {
+        for prop in obj.props.iter_mut() {
+            if !utils::is_converted_option(prop) {
+                continue;
+            }
+            let prop = match prop {
+                PropOrSpread::Prop(prop) => prop,
+                PropOrSpread::Spread(_) => continue,
+            };
+
+            match &mut **prop {
+                // The entries of computed, watch and methods are options of their own
+                Prop::KeyValue(kv)
+                    if kv.key.as_ident().is_some_and(|key| {
+                        matches!(&*key.sym, "computed" | "watch" | "methods")
+                    }) =>
                 {
-                    // Simply replace this.method() with method(),
-                    // excluding any special functions
-                    if !self.special_functions.contains(&id.sym.to_string()) {
-                        call_expr.callee = Callee::Expr(Box::new(Expr::Ident(id.clone())));
+                    let option = kv.key.as_ident().unwrap().sym.to_string();
+                    match &mut *kv.value {
+                        Expr::Object(entries) => {
+                            for entry in entries.props.iter_mut() {
+                                // Entries that are passed through still run with `this`
+                                let converted = match option.as_str() {
+                                    "computed" => entry
+                                        .as_prop()
+                                        .is_some_and(|prop| utils::computed_decl(prop).is_some()),
+                                    "watch" => self.watcher_problem(entry).is_none(),
+                                    _ => entry
+                                        .as_prop()
+                                        .is_some_and(|prop| utils::method_decl(prop).is_some()),
+                                };
+
+                                match entry {
+                                    _ if !converted => {}
+                                    PropOrSpread::Prop(entry) if option == "computed" => {
+                                        self.visit_mut_computed_entry(entry)
+                                    }
+                                    PropOrSpread::Prop(entry) => self.visit_mut_option_entry(entry),
+                                    spread => spread.visit_mut_with(self),
+                                }
+                            }
+                        }
+                        value => value.visit_mut_with(self),
                     }
                 }
+                // data() is passed the instance too, e.g. `data: (vm) => ({ id: vm.initialId })`
+                option
+                    if utils::option_key_ident(option).is_some_and(|key| &*key.sym == "data") =>
+                {
+                    self.visit_mut_computed_getter(option)
+                }
+                option => self.visit_mut_option(option),
             }
         }
+    }
 
-        // Visit children after top level processing, since calls are
-        // higher up in the AST, and we're removing the `this` expression
-        call_expr.visit_mut_children_with(self);
+    /**
+     * Visit an entry of watch or methods, which may be an object like `{ handler() {} }` or an
+     * array of handlers
+     */
+    fn visit_mut_option_entry(&mut self, entry: &mut Prop) {
+        if let Prop::KeyValue(KeyValueProp { value, .. }) = entry {
+            match &mut **value {
+                Expr::Object(obj) => {
+                    self.visit_mut_handler_object(obj);
+                    return;
+                }
+                Expr::Array(handlers) => {
+                    for handler in handlers.elems.iter_mut().flatten() {
+                        match &mut *handler.expr {
+                            Expr::Object(obj) => self.visit_mut_handler_object(obj),
+                            Expr::Fn(fn_expr) => {
+                                self.visit_mut_bound_function(&mut fn_expr.function)
+                            }
+                            handler => handler.visit_mut_with(self),
+                        }
+                    }
+                    return;
+                }
+                _ => {}
+            }
+        }
+
+        self.visit_mut_option(entry)
     }
 
-    // This will convert all uses of `this` to the corresponding refs
-    fn visit_mut_member_expr(&mut self, member_expr: &mut MemberExpr) {
-        // Visit children before top level processing
-        member_expr.visit_mut_children_with(self);
+    /** Visit an object like `{ handler() {}, deep: true }`, whose functions are options */
+    fn visit_mut_handler_object(&mut self, obj: &mut ObjectLit) {
+        for prop in obj.props.iter_mut() {
+            match prop {
+                PropOrSpread::Prop(prop) => self.visit_mut_option(prop),
+                spread => spread.visit_mut_with(self),
+            }
+        }
+    }
 
-        // Handle injects, since they convert member expressions to idents
-        // If a `this` expression is found here, it means it's an inject, since
-        // otherwise it would have been transformed deeper within the tree before reaching here
-        if let (Expr::Member(nested_member_expr), MemberProp::Ident(_)) =
-            (&*member_expr.obj, &member_expr.prop)
-        {
-            if let (Expr::This(_), MemberProp::Ident(nested_id)) =
-                (&*nested_member_expr.obj, &nested_member_expr.prop)
-            {
-                member_expr.obj = Box::new(Expr::Ident(Ident {
-                    optional: false,
-                    span: Default::default(),
-                    sym: nested_id.sym.clone(),
-                }))
+    /** Visit an entry of computed, which may be a getter or an object like `{ get() {}, set(v) {} }` */
+    fn visit_mut_computed_entry(&mut self, entry: &mut Prop) {
+        if let Prop::KeyValue(KeyValueProp { value, .. }) = entry {
+            if let Expr::Object(obj) = &mut **value {
+                for prop in obj.props.iter_mut() {
+                    match prop {
+                        PropOrSpread::Prop(prop)
+                            if utils::option_key_ident(prop)
+                                .is_some_and(|key| &*key.sym == "get") =>
+                        {
+                            self.visit_mut_computed_getter(prop)
+                        }
+                        PropOrSpread::Prop(prop) => self.visit_mut_option(prop),
+                        spread => spread.visit_mut_with(self),
+                    }
+                }
+                return;
             }
         }
 
-        // Handle most nested case
-        if let (Expr::This(_), MemberProp::Ident(id)) = (&*member_expr.obj, &mut member_expr.prop) {
-            // Check if id is an inject
-            if let Some(injects) = &self.inject_set {
-                if injects.contains_key(&id.sym.to_string()) {
-                    // Don't do anything, this is handled higher up the tree
-                    return;
+        self.visit_mut_computed_getter(entry)
+    }
+
+    /**
+     * Visit a computed getter or `data`. They're passed the instance, so their parameter is an
+     * alias of `this`, which is how arrow functions reach the component, e.g.
+     * `double: (vm) => vm.count * 2`.
+     */
+    fn visit_mut_computed_getter(&mut self, getter: &mut Prop) {
+        if let Some(param) = utils::first_param_ident(getter) {
+            self.aliases.insert(param.sym.to_string(), Alias::This);
+        }
+
+        self.visit_mut_option(getter);
+        self.aliases.clear();
+    }
+
+    /** Visit an option, where a function binds `this` to the component instance */
+    fn visit_mut_option(&mut self, option: &mut Prop) {
+        let function = match option {
+            Prop::Method(method) => &mut method.function,
+            Prop::KeyValue(KeyValueProp { value, .. }) if value.is_fn_expr() => {
+                &mut value.as_mut_fn_expr().unwrap().function
+            }
+            // Its parameters may be aliases of `this`, so they don't shadow them
+            Prop::KeyValue(KeyValueProp { value, .. }) if value.is_arrow() => {
+                value.as_mut_arrow().unwrap().visit_mut_children_with(self);
+                return;
+            }
+            option => {
+                option.visit_mut_with(self);
+                return;
+            }
+        };
+
+        self.visit_mut_bound_function(function)
+    }
+
+    /** Visit a function whose `this` is the component instance */
+    fn visit_mut_bound_function(&mut self, function: &mut Function) {
+        // Its parameters may be aliases of `this`, so they're only tracked as declarations
+        self.scopes.push(utils::function_scope_names(
+            function.params.iter().map(|param| &param.pat),
+            function.body.as_ref(),
+            true,
+        ));
+        self.this_is_component = true;
+        function.visit_mut_children_with(self);
+        self.this_is_component = false;
+        self.aliases.clear();
+        self.scopes.pop();
+    }
+
+    /**
+     * Visit a scope that declares `names`. Aliases of the same names are shadowed, so they aren't
+     * rewritten inside it.
+     */
+    fn visit_mut_scope(&mut self, names: Vec<String>, visit: impl FnOnce(&mut Self)) {
+        let shadowed: Vec<(String, Alias)> = names
+            .iter()
+            .filter_map(|name| {
+                let alias = self.aliases.remove(name)?;
+                Some((name.clone(), alias))
+            })
+            .collect();
+        self.scopes.push(names);
+        visit(self);
+        self.scopes.pop();
+        self.aliases.extend(shadowed);
+    }
+
+    /**
+     * An alias of `this` used on its own, like `registry.add(self)`. Setup has no `this`, so it's
+     * replaced by the instance proxy from `getCurrentInstance()`.
+     */
+    fn escaped_alias(&mut self, alias: &Ident) -> Expr {
+        self.diagnostics.push(
+            Diagnostic::warning(
+                "escaped-this-alias",
+                format!(
+                    "`{}` is the component instance, which setup doesn't have, it was replaced by `getCurrentInstance().proxy`",
+                    alias.sym
+                ),
+            )
+            .with_span(alias.span),
+        );
+
+        self.composition.uses_instance = true;
+        let expr = Expr::Ident(Ident {
+            span: alias.span,
+            ..utils::ident(write::INSTANCE_IDENT)
+        });
+        self.check_shadowed(&alias.sym, &expr, alias.span);
+        expr
+    }
+
+    /** Whether a local declaration in scope has the name `name` */
+    fn is_shadowed(&self, name: &str) -> bool {
+        self.scopes.iter().flatten().any(|local| local == name)
+    }
+
+    /**
+     * Report an error if `original`, rewritten as `expr`, starts with a name that a local
+     * declaration shadows, e.g. `items.value = items` in `setItems(items)`
+     */
+    fn check_shadowed(&mut self, original: &str, expr: &Expr, span: Span) {
+        let root = match utils::root_ident(expr) {
+            Some(root) if self.is_shadowed(&root.sym) => root.sym.to_string(),
+            _ => return,
+        };
+
+        self.diagnostics.push(
+            Diagnostic::error(
+                "shadowed-member",
+                format!(
+                    "`{}` was rewritten to use `{}`, which a local declaration shadows here, rename the local",
+                    original, root
+                ),
+            )
+            .with_span(span),
+        );
+    }
+
+    /** The member of the component `expr` reads, for `this.x` where `this` is bound or `self.x` */
+    fn component_member_name(&self, expr: &Expr) -> Option<Ident> {
+        if let Some((obj, prop)) = utils::ident_member(expr) {
+            if self.aliases.get(&*obj.sym) == Some(&Alias::This) {
+                return Some(prop.clone());
+            }
+        }
+
+        utils::this_member_name(expr).filter(|_| self.this_is_component)
+    }
+
+    /**
+     * Split a declarator initialized with `this`, recording aliases, or return `None` if it can't
+     * be split. `const self = this` is dropped, and `const { a, b: c } = this` becomes a declarator
+     * per member. A member whose local name is the name it's rewritten to, like data or methods,
+     * would be shadowed by its own declaration, so it becomes an alias instead.
+     */
+    fn split_this_declarator(&mut self, decl: &VarDeclarator) -> Option<Vec<VarDeclarator>> {
+        let obj = match &decl.name {
+            Pat::Ident(alias) => {
+                self.aliases.insert(alias.id.sym.to_string(), Alias::This);
+                return Some(vec![]);
+            }
+            Pat::Object(obj) => obj,
+            _ => return None,
+        };
+
+        let mut members = vec![];
+        for prop in obj.props.iter() {
+            match prop {
+                ObjectPatProp::Assign(AssignPatProp {
+                    key, value: None, ..
+                }) => members.push((key.clone(), key.clone())),
+                ObjectPatProp::KeyValue(KeyValuePatProp { key, value })
+                    if value.is_ident() && utils::ident_from_prop_name(key).is_some() =>
+                {
+                    let key = utils::ident_from_prop_name(key).unwrap();
+                    members.push((key, value.as_ident().unwrap().id.clone()))
                 }
+                _ => {
+                    self.diagnostics.push(
+                        Diagnostic::warning(
+                            "unsupported-this-destructure",
+                            "defaults, rest and nested patterns can't be destructured from `this`, the declaration was left as is",
+                        )
+                        .with_span(decl.span),
+                    );
+                    return None;
+                }
+            }
+        }
+
+        let mut decls = vec![];
+        for (key, local) in members {
+            let shadowed = local.sym == key.sym
+                && matches!(
+                    self.bindings.get(&key.sym),
+                    None | Some(
+                        Binding::Data | Binding::Computed | Binding::Method | Binding::Inject
+                    )
+                );
+            if shadowed {
+                self.aliases
+                    .insert(local.sym.to_string(), Alias::Member(key));
+                continue;
+            }
+
+            let span = key.span;
+            decls.push(VarDeclarator {
+                span,
+                name: Pat::Ident(local.into()),
+                init: Some(Box::new(self.rewrite_this_member(key, span))),
+                definite: false,
+            });
+        }
+
+        Some(decls)
+    }
+}
+
+// This is used for the AST modification
+impl VisitMut for Visitor {
+    fn visit_mut_call_expr(&mut self, call_expr: &mut CallExpr) {
+        if let Callee::Expr(callee) = &call_expr.callee {
+            if self
+                .component_member_name(callee)
+                .is_some_and(|name| &*name.sym == "$emit")
+            {
+                self.process_emit(call_expr);
             }
+        }
+
+        call_expr.visit_mut_children_with(self);
+    }
 
-            // Handle props
-            if let Some(props) = &self.props_set {
-                // Replace `this` with `props` if its in the props set
-                if props.contains(&id.sym.to_string()) {
-                    member_expr.obj = Box::new(Expr::Ident(Ident {
-                        optional: false,
-                        span: Default::default(),
-                        sym: Atom::from("props"),
-                    }));
+    // This will convert all uses of `this.x`, according to what `x` is
+    fn visit_mut_expr(&mut self, expr: &mut Expr) {
+        if let Expr::Call(call_expr) = expr {
+            if let Some(rewritten) = self.rewrite_set_delete(call_expr) {
+                *expr = rewritten;
+                return;
+            }
+        }
 
-                    // Exit early
+        if let Expr::Member(MemberExpr {
+            obj,
+            prop: MemberProp::Ident(key),
+            ..
+        }) = expr
+        {
+            let member = self.component_member_name(obj).map(|name| name.sym);
+            let member = member.as_deref().unwrap_or_default();
+            let original = format!("this.{}.{}", member, key.sym);
+            let span = key.span;
+            match member {
+                // this.$refs.foo -> foo.value
+                "$refs" => {
+                    let name = self.template_ref(key);
+                    *expr = utils::member_expr(Expr::Ident(name), utils::ident("value"));
+                    self.check_shadowed(&original, expr, span);
                     return;
                 }
+                // this.$listeners.click -> ctx.attrs.onClick, Vue 3 passes listeners as attrs
+                "$listeners" if self.target.context_property("$listeners") == Some("attrs") => {
+                    let listener = Ident {
+                        span,
+                        ..utils::ident(&utils::listener_prop(&key.sym))
+                    };
+                    let attrs = self.context_property("attrs");
+                    *expr = utils::member_expr(attrs, listener);
+                    self.check_shadowed(&original, expr, span);
+                    return;
+                }
+                _ => {}
             }
+        }
 
-            // Handle $emit
-            let value_string = id.sym.to_string();
-            if value_string.as_str() == "$emit" {
-                member_expr.obj = Box::new(Expr::Ident(Ident {
-                    optional: false,
-                    span: Default::default(),
-                    sym: Atom::from("ctx"),
-                }));
+        // `self.x` is rewritten as a whole, so `self` isn't reported as escaping
+        if let Some(name) = self.component_member_name(expr) {
+            *expr = self.rewrite_this_member(name, expr.span());
+            return;
+        }
 
-                // Exit early
-                return;
+        // Visit children before top level processing
+        expr.visit_mut_children_with(self);
+
+        if let Expr::Ident(ident) = expr {
+            match self.aliases.get(&*ident.sym).cloned() {
+                Some(Alias::Member(name)) => *expr = self.rewrite_this_member(name, ident.span),
+                Some(Alias::This) => *expr = self.escaped_alias(&ident.clone()),
+                None => {}
             }
+        }
 
-            // Handle arbitrary global props
-            if value_string.as_str().chars().next().unwrap() == '$' {
-                // Convert this.$foo to ctx.$root.foo
-                member_expr.obj = Box::new(Expr::Member(MemberExpr {
-                    span: Default::default(),
-                    obj: Box::new(Expr::Ident(Ident {
-                        optional: false,
-                        span: Default::default(),
-                        sym: Atom::from("ctx"),
-                    })),
-                    prop: MemberProp::Ident(Ident {
-                        optional: false,
-                        span: Default::default(),
-                        sym: Atom::from("$root"),
-                    }),
-                }));
-
-                // Remove `$` from ident
-                id.sym = Atom::from(&value_string[1..]);
-
-                // Exit early
+        // Any `this.x` left is in a function that binds its own `this`
+        if let Some(name) = utils::this_member_name(expr) {
+            self.diagnostics.push(
+                Diagnostic::warning(
+                    "unbound-this",
+                    format!(
+                        "`this` is not the component instance here, `this.{}` was left as is",
+                        name.sym
+                    ),
+                )
+                .with_span(expr.span()),
+            );
+        }
+    }
+
+    // `{ items }`, where `items` was destructured from `this`
+    fn visit_mut_prop(&mut self, prop: &mut Prop) {
+        if let Prop::Shorthand(ident) = prop {
+            let value = match self.aliases.get(&*ident.sym).cloned() {
+                Some(Alias::Member(name)) => Some(self.rewrite_this_member(name, ident.span)),
+                Some(Alias::This) => Some(self.escaped_alias(ident)),
+                None => None,
+            };
+            if let Some(value) = value {
+                *prop = Prop::KeyValue(KeyValueProp {
+                    key: PropName::Ident(ident.clone()),
+                    value: Box::new(value),
+                });
                 return;
             }
+        }
 
-            // Default case, treat as ref
-            member_expr.obj = Box::new(Expr::Ident(id.clone()));
-            member_expr.prop = MemberProp::Ident(Ident {
-                optional: false,
-                span: Default::default(),
-                sym: Atom::from("value"),
-            });
+        prop.visit_mut_children_with(self);
+    }
+
+    // Declarators initialized with `this` are split into aliases and members
+    fn visit_mut_var_decl(&mut self, var_decl: &mut VarDecl) {
+        for mut decl in std::mem::take(&mut var_decl.decls) {
+            if self.this_is_component && decl.init.as_deref().is_some_and(Expr::is_this) {
+                if let Some(decls) = self.split_this_declarator(&decl) {
+                    var_decl.decls.extend(decls);
+                    continue;
+                }
+            }
+
+            decl.visit_mut_with(self);
+            var_decl.decls.push(decl);
         }
     }
 
+    // Drop the declarations left empty by splitting
+    fn visit_mut_stmts(&mut self, stmts: &mut Vec<Stmt>) {
+        stmts.visit_mut_children_with(self);
+        stmts.retain(|stmt| !matches!(stmt, Stmt::Decl(Decl::Var(var)) if var.decls.is_empty()));
+    }
+
+    // Functions, getters, setters and classes bind their own `this`, arrow functions don't
+    fn visit_mut_function(&mut self, function: &mut Function) {
+        let this_is_component = std::mem::replace(&mut self.this_is_component, false);
+        let names = utils::function_scope_names(
+            function.params.iter().map(|param| &param.pat),
+            function.body.as_ref(),
+            false,
+        );
+        self.visit_mut_scope(names, |visitor| function.visit_mut_children_with(visitor));
+        self.this_is_component = this_is_component;
+    }
+
+    fn visit_mut_getter_prop(&mut self, getter: &mut GetterProp) {
+        let this_is_component = std::mem::replace(&mut self.this_is_component, false);
+        let names = utils::function_scope_names([], getter.body.as_ref(), false);
+        self.visit_mut_scope(names, |visitor| getter.visit_mut_children_with(visitor));
+        self.this_is_component = this_is_component;
+    }
+
+    fn visit_mut_setter_prop(&mut self, setter: &mut SetterProp) {
+        let this_is_component = std::mem::replace(&mut self.this_is_component, false);
+        let names = utils::function_scope_names([&setter.param], setter.body.as_ref(), false);
+        self.visit_mut_scope(names, |visitor| setter.visit_mut_children_with(visitor));
+        self.this_is_component = this_is_component;
+    }
+
+    fn visit_mut_class(&mut self, class: &mut Class) {
+        let this_is_component = std::mem::replace(&mut self.this_is_component, false);
+        class.visit_mut_children_with(self);
+        self.this_is_component = this_is_component;
+    }
+
+    // Declarations shadow the aliases of the same names in the scope they're in
+    fn visit_mut_arrow_expr(&mut self, arrow: &mut ArrowExpr) {
+        let body = match &arrow.body {
+            BlockStmtOrExpr::BlockStmt(block) => Some(block),
+            BlockStmtOrExpr::Expr(_) => None,
+        };
+        let names = utils::function_scope_names(&arrow.params, body, self.this_is_component);
+        self.visit_mut_scope(names, |visitor| arrow.visit_mut_children_with(visitor));
+    }
+
+    fn visit_mut_fn_expr(&mut self, fn_expr: &mut FnExpr) {
+        let names = fn_expr.ident.iter().map(|ident| ident.sym.to_string());
+        self.visit_mut_scope(names.collect(), |visitor| {
+            fn_expr.visit_mut_children_with(visitor)
+        });
+    }
+
+    fn visit_mut_class_expr(&mut self, class_expr: &mut ClassExpr) {
+        let names = class_expr.ident.iter().map(|ident| ident.sym.to_string());
+        self.visit_mut_scope(names.collect(), |visitor| {
+            class_expr.visit_mut_children_with(visitor)
+        });
+    }
+
+    fn visit_mut_block_stmt(&mut self, block: &mut BlockStmt) {
+        let names = utils::block_scope_names(&block.stmts, self.this_is_component);
+        self.visit_mut_scope(names, |visitor| block.visit_mut_children_with(visitor));
+    }
+
+    fn visit_mut_catch_clause(&mut self, catch: &mut CatchClause) {
+        let names = utils::pat_names(&catch.param);
+        self.visit_mut_scope(names, |visitor| catch.visit_mut_children_with(visitor));
+    }
+
+    fn visit_mut_for_stmt(&mut self, for_stmt: &mut ForStmt) {
+        let head = match &for_stmt.init {
+            Some(VarDeclOrExpr::VarDecl(var)) => Some(var),
+            _ => None,
+        };
+        let names = utils::loop_head_names(head);
+        self.visit_mut_scope(names, |visitor| for_stmt.visit_mut_children_with(visitor));
+    }
+
+    fn visit_mut_for_in_stmt(&mut self, for_in: &mut ForInStmt) {
+        let names = utils::loop_head_names(for_in.left.as_var_decl());
+        self.visit_mut_scope(names, |visitor| for_in.visit_mut_children_with(visitor));
+    }
+
+    fn visit_mut_for_of_stmt(&mut self, for_of: &mut ForOfStmt) {
+        let names = utils::loop_head_names(for_of.left.as_var_decl());
+        self.visit_mut_scope(names, |visitor| for_of.visit_mut_children_with(visitor));
+    }
+
     fn visit_mut_module(&mut self, module: &mut Module) {
         // Preprocess before mutating module
         module.visit_with(self);
 
-        // Visit children before top level processing
-        module.visit_mut_children_with(self);
-
         // Find default export
         let res = module.body.iter().enumerate().find_map(|(index, item)| {
             if !item.is_module_decl() {
@@ -193,11 +868,7 @@ impl VisitMut for Visitor {
             }
 
             let expr = decl.as_export_default_expr().unwrap();
-            if !expr.expr.is_object() {
-                return None;
-            }
-
-            return Some((index, expr.expr.as_object().unwrap().clone()));
+            Some((index, utils::component_object(&expr.expr)?.clone()))
         });
 
         // Exit if not found
@@ -205,22 +876,117 @@ impl VisitMut for Visitor {
             return;
         }
 
-        // Process default export props
-        let (default_export_index, default_export) = res.unwrap();
+        // Exit if there's nothing to convert, `<script setup>` has macros for the kept options too
+        let (default_export_index, mut default_export) = res.unwrap();
+        let converts = match self.script_setup {
+            true => utils::is_converted_option,
+            false => utils::is_setup_option,
+        };
+        if !default_export.props.iter().any(converts) {
+            return;
+        }
+
+        // Rewrite `this` within the component, then process its options
+        self.visit_mut_component(&mut default_export);
+        self.found_component = true;
         self.process_default_export(&default_export);
 
         // Run all transformations between options and composition API
         // dbg!(&self.composition);
-        self.transform_component();
+        if let Err(err) = self.transform_component() {
+            self.error = Some(err);
+            return;
+        }
+
+        // Convert, keeping the original span so comments on the export are kept
+        let export_span = module.body[default_export_index].span();
+        if self.script_setup {
+            let stmts = write::write_script_setup(&self.composition, self.target);
+            if let (Some(comments), Some(first)) = (&self.comments, stmts.first()) {
+                utils::move_leading_comments(comments, export_span.lo, first.span().lo);
+            }
+            module.body.splice(
+                default_export_index..=default_export_index,
+                stmts.into_iter().map(ModuleItem::Stmt),
+            );
+
+            // `<script setup>` can't export anything, named exports go to a plain `<script>`
+            let (exports, body) = module.body.drain(..).partition(utils::is_value_export);
+            module.body = body;
+            self.plain_script = write::write_plain_script(&self.composition, self.target, exports);
+        } else {
+            let mut export = write::write_composition_component(&self.composition);
+            export.span = export_span;
+            module.body[default_export_index] =
+                ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultExpr(export));
+        }
 
-        // Convert
-        module.body[default_export_index] = ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultExpr(
-            write::write_composition_component(&self.composition),
-        ))
+        // Import the APIs the converted component uses
+        let diagnostics = write::write_imports(
+            module,
+            self.target.import_source(),
+            &self.composition.imports,
+            self.comments.as_ref(),
+        );
+        self.diagnostics.extend(diagnostics);
     }
 }
 
-pub fn visit_module(module: Module) -> Module {
+/** A module whose Options API component was converted */
+#[derive(Debug)]
+pub struct ConvertedModule {
+    pub module: Module,
+
+    // The names of the component options that were converted, e.g. `data` or `watch`
+    pub handled: Vec<&'static str>,
+
+    // What `<script setup>` can't hold, for a plain `<script>` block next to it
+    pub plain_script: Option<Module>,
+}
+
+/**
+ * Convert the component in a module as described by `options`, returning `None` if no Options API
+ * component was found.
+ * Comments are moved along with the code they describe, and any warnings found along the way
+ * are pushed onto `diagnostics`.
+ */
+pub fn visit_module(
+    mut module: Module,
+    options: &Options,
+    comments: Option<&SingleThreadedComments>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<Option<ConvertedModule>, OtcError> {
     // dbg!(&module);
-    module.fold_with(&mut as_folder(Visitor::default()))
+    let mut visitor = Visitor {
+        target: options.target,
+        script_setup: options.script_setup,
+        typed_props: options.typed_props && options.script_setup && options.typescript,
+        comments: comments.cloned(),
+        composition: vue::CompositionComponent {
+            destructure_context: options.destructure_context,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    module.visit_mut_with(&mut visitor);
+    diagnostics.append(&mut visitor.diagnostics);
+
+    if let Some(err) = visitor.error {
+        return Err(err);
+    }
+
+    if !visitor.found_component {
+        return Ok(None);
+    }
+
+    let plain_script = (!visitor.plain_script.is_empty()).then(|| Module {
+        span: Default::default(),
+        body: visitor.plain_script,
+        shebang: None,
+    });
+    Ok(Some(ConvertedModule {
+        module,
+        handled: visitor.options.handled_options(),
+        plain_script,
+    }))
 }
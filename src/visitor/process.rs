@@ -1,26 +1,103 @@
 use super::utils;
-use super::vue::WatchDecl;
+use super::vue::{Binding, ComputedDecl, WatchCallback, WatchDecl, LIFECYCLE_HOOKS};
 use super::Visitor;
+use crate::diagnostics::Diagnostic;
 
+use swc_common::{Span, Spanned};
 use swc_ecma_ast::*;
 
 impl Visitor {
+    /** Record the event emitted by a `this.$emit(...)` call */
+    pub fn process_emit(&mut self, call_expr: &CallExpr) {
+        self.composition.calls_emit = true;
+        match call_expr.args.first().map(|arg| &*arg.expr) {
+            Some(Expr::Lit(Lit::Str(event))) => {
+                let event = event.value.to_string();
+                if !self.composition.emitted_events.contains(&event) {
+                    self.composition.emitted_events.push(event);
+                }
+            }
+            _ => self.dynamic_emits.push(call_expr.span()),
+        }
+    }
+
     pub fn preprocess_default_export(&mut self, object: &ObjectLit) {
-        // Build set of prop IDs
+        // Build the table of everything reachable through `this`
         for x in object.props.iter() {
             if let Some(prop) = x.as_prop() {
-                if let Prop::KeyValue(kv) = &**prop {
-                    if let Some(ident) = kv.key.as_ident() {
-                        match ident.sym.to_string().as_str() {
-                            "props" => {
-                                self.props_set = utils::prop_set_from_object_lit(&kv.value);
-                            }
-                            "inject" => {
-                                self.inject_set = utils::inject_set_from_object_lit(&kv.value);
+                match &**prop {
+                    Prop::KeyValue(kv) => {
+                        if let Some(ident) = kv.key.as_ident() {
+                            match ident.sym.to_string().as_str() {
+                                "props" => {
+                                    for name in utils::prop_set_from_object_lit(&kv.value)
+                                        .unwrap_or_default()
+                                    {
+                                        self.bindings.insert(name, Binding::Prop);
+                                    }
+                                }
+                                "inject" => {
+                                    self.inject_set = utils::inject_set_from_object_lit(&kv.value);
+                                    for name in self.inject_set.iter().flat_map(|set| set.keys()) {
+                                        self.bindings.insert(name.clone(), Binding::Inject);
+                                    }
+                                }
+                                "computed" => {
+                                    for name in utils::object_keys(&kv.value) {
+                                        self.bindings.insert(name, Binding::Computed);
+                                    }
+                                }
+                                "data" => {
+                                    let function = utils::data_function(&kv.value);
+                                    for name in function.iter().flat_map(utils::data_keys) {
+                                        self.bindings.insert(name, Binding::Data);
+                                    }
+                                }
+                                "methods" => {
+                                    // Methods that are passed through stay on the instance
+                                    let entries = kv.value.as_object().map(|obj| &obj.props);
+                                    for prop in entries.into_iter().flatten() {
+                                        let prop = match prop.as_prop() {
+                                            Some(prop) => prop,
+                                            None => continue,
+                                        };
+                                        let binding = match utils::method_decl(prop) {
+                                            Some(_) => Binding::Method,
+                                            None => Binding::InstanceProperty,
+                                        };
+                                        if let Some(name) = utils::prop_key(prop) {
+                                            self.bindings.insert(name, binding);
+                                        }
+                                    }
+                                }
+                                _ => {}
                             }
-                            _ => {}
                         }
                     }
+                    Prop::Method(method)
+                        if method
+                            .key
+                            .as_ident()
+                            .is_some_and(|ident| &*ident.sym == "data") =>
+                    {
+                        for name in utils::data_keys(&method.function) {
+                            self.bindings.insert(name, Binding::Data);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    /** Record `function` if `name` is a lifecycle hook */
+    pub fn process_lifecycle_hook(&mut self, name: &str, function: &Function) {
+        match name {
+            "beforeCreate" => self.options.before_create = Some(function.clone()),
+            "created" => self.options.created = Some(function.clone()),
+            _ => {
+                if let Some((hook, _)) = LIFECYCLE_HOOKS.iter().find(|(hook, _)| *hook == name) {
+                    self.options.hooks.push((hook, function.clone()));
                 }
             }
         }
@@ -32,134 +109,219 @@ impl Visitor {
                 "data" => {
                     self.options.data = Some(method_prop.function.clone());
                 }
-                "created" => {
-                    self.options.created = Some(method_prop.function.clone());
-                }
-                "mounted" => {
-                    self.options.mounted = Some(method_prop.function.clone());
+                name => {
+                    self.process_lifecycle_hook(name, &method_prop.function);
                 }
-                _ => {}
             }
         }
     }
 
     pub fn process_computed(&mut self, obj: &ObjectLit) {
-        let mut computed_decls: Vec<FnDecl> = vec![];
+        let mut computed_decls: Vec<ComputedDecl> = vec![];
+        let mut passed_through: Vec<PropOrSpread> = vec![];
         for prop in obj.props.iter() {
-            if let PropOrSpread::Prop(boxed_expr) = prop {
-                if let Prop::Method(method_expr) = &**boxed_expr {
-                    if let PropName::Ident(ident) = &method_expr.key {
-                        computed_decls.push(FnDecl {
-                            ident: ident.clone(),
-                            declare: false,
-                            function: method_expr.function.clone(),
-                        });
-                    }
+            match prop.as_prop().and_then(|prop| utils::computed_decl(prop)) {
+                Some(decl) => computed_decls.push(decl),
+                None => {
+                    self.diagnostics.push(
+                        Diagnostic::note(
+                            "option-passed-through",
+                            "computed property is not a getter or a `{ get, set }` object, it was copied to a `computed` option as is",
+                        )
+                        .with_span(prop.span()),
+                    );
+                    passed_through.push(prop.clone());
                 }
             }
         }
 
+        // Entries like `...mapGetters([])` are kept in a computed option of their own
+        self.pass_through_entries("computed", obj.span, passed_through);
+
         // Add to component
-        if computed_decls.len() > 0 {
+        if !computed_decls.is_empty() {
             self.options.computed = Some(computed_decls);
         }
     }
 
-    pub fn process_watch(&mut self, obj: &ObjectLit) {
-        let mut watch_decls: Vec<WatchDecl> = vec![];
-        for prop in obj.props.iter() {
-            if let PropOrSpread::Prop(boxed_expr) = prop {
-                // Method case
-                if let Prop::Method(method_expr) = &**boxed_expr {
-                    if let PropName::Ident(ident) = &method_expr.key {
-                        watch_decls.push(WatchDecl {
-                            ident: ident.clone(),
-                            function: method_expr.function.clone(),
-                            deep: None,
-                            immediate: None,
-                        });
-                    }
-                }
+    /** The segments of a watched path like `'form.name'`, if it's a member of the component */
+    fn watch_path<'a>(&self, path: &'a str) -> Option<(Vec<&'a str>, Binding)> {
+        let segments: Vec<&str> = path.split('.').collect();
+        if segments
+            .iter()
+            .any(|segment| Ident::verify_symbol(segment).is_err())
+        {
+            return None;
+        }
 
-                // Complex case
-                if let Prop::KeyValue(kv_prop) = &**boxed_expr {
-                    if let Expr::Object(obj) = &*kv_prop.value {
-                        if let PropName::Ident(ident) = &kv_prop.key {
-                            let mut function: Option<Function> = None;
-                            let mut immediate: Option<Box<Expr>> = None;
-                            let mut deep: Option<Box<Expr>> = None;
+        let binding = self.bindings.get(segments[0])?;
+        Some((segments, binding))
+    }
 
-                            // Find handler, deep, and immediate
-                            for item in &obj.props {
-                                if !item.is_prop() {
-                                    continue;
-                                }
-                                let prop = item.as_prop().unwrap();
-
-                                match &**prop {
-                                    // Check for handler method
-                                    Prop::Method(method) => {
-                                        if let PropName::Ident(inner_ident) = &method.key {
-                                            if inner_ident.sym.to_string().as_str() == "handler" {
-                                                function = Some(method.function.clone());
-                                            }
-                                        }
-                                    }
-                                    // Check for deep/immediate
-                                    Prop::KeyValue(inner_kv) => {
-                                        if let PropName::Ident(inner_ident) = &inner_kv.key {
-                                            if inner_ident.sym.to_string().as_str() == "deep" {
-                                                deep = Some(inner_kv.value.clone());
-                                            }
-
-                                            if inner_ident.sym.to_string().as_str() == "immediate" {
-                                                immediate = Some(inner_kv.value.clone());
-                                            }
-                                        }
-                                    }
-                                    _ => {}
-                                }
-                            }
+    /**
+     * The source a watcher keyed `path` watches. Data and computed refs are watched directly,
+     * anything else through a getter, e.g. `() => props.id` or `() => form.value.name` for
+     * `'form.name'`. Returns `None` if the path isn't a member of the component.
+     */
+    fn watch_source(&mut self, path: &str, span: Span) -> Option<Box<Expr>> {
+        let (segments, binding) = self.watch_path(path)?;
+        let root = Ident {
+            span,
+            ..utils::ident(segments[0])
+        };
+        if segments.len() == 1 && matches!(binding, Binding::Data | Binding::Computed) {
+            return Some(Box::new(Expr::Ident(root)));
+        }
 
-                            // Check
-                            if let Some(func) = function {
-                                watch_decls.push(WatchDecl {
-                                    ident: ident.clone(),
-                                    function: func,
-                                    immediate,
-                                    deep,
-                                })
-                            }
-                        }
-                    }
-                }
+        let getter = segments[1..]
+            .iter()
+            .fold(self.rewrite_this_member(root, span), |obj, segment| {
+                utils::member_expr(obj, utils::ident(segment))
+            });
+        Some(Box::new(Expr::Arrow(ArrowExpr {
+            span: Default::default(),
+            params: vec![],
+            body: BlockStmtOrExpr::Expr(Box::new(getter)),
+            is_async: false,
+            is_generator: false,
+            type_params: None,
+            return_type: None,
+        })))
+    }
+
+    /**
+     * Why an entry of watch can't be converted, or `None` if it can. Such watchers are passed
+     * through, and still run with `this`.
+     */
+    pub fn watcher_problem(&self, entry: &PropOrSpread) -> Option<Diagnostic> {
+        let watcher = entry
+            .as_prop()
+            .and_then(|prop| Some((utils::watch_key(prop)?, utils::watch_handlers(prop)?)));
+        let ((key, key_span), handlers) = match watcher {
+            Some(watcher) => watcher,
+            None => {
+                return Some(
+                    Diagnostic::note(
+                        "option-passed-through",
+                        "watcher has no handler that can be converted, it was copied to a `watch` option as is",
+                    )
+                    .with_span(entry.span()),
+                )
+            }
+        };
+
+        // Handlers named by a string must be methods of the component
+        let unknown_method = handlers.iter().find_map(|handler| match &handler.callback {
+            WatchCallback::Method(name)
+                if self.bindings.get(&name.sym) != Some(Binding::Method) =>
+            {
+                Some(name.clone())
             }
+            _ => None,
+        });
+        if let Some(name) = unknown_method {
+            return Some(
+                Diagnostic::warning(
+                    "unresolved-watch-handler",
+                    format!(
+                        "`{}` is not a method of the component, the watcher was copied to a `watch` option as is",
+                        name.sym
+                    ),
+                )
+                .with_span(name.span),
+            );
+        }
+
+        if self.watch_path(&key).is_none() {
+            return Some(
+                Diagnostic::warning(
+                    "unresolved-watch-source",
+                    format!(
+                        "`{}` can't be resolved to a member of the component, the watcher was copied to a `watch` option as is",
+                        key
+                    ),
+                )
+                .with_span(key_span),
+            );
+        }
+
+        None
+    }
+
+    /** Keep entries of `option` that can't be converted in an option of their own, next to setup */
+    fn pass_through_entries(&mut self, option: &str, span: Span, entries: Vec<PropOrSpread>) {
+        if entries.is_empty() {
+            return;
         }
 
+        self.options
+            .passed_through
+            .push(PropOrSpread::Prop(Box::new(Prop::KeyValue(KeyValueProp {
+                key: PropName::Ident(utils::ident(option)),
+                value: Box::new(Expr::Object(ObjectLit {
+                    span,
+                    props: entries,
+                })),
+            }))));
+    }
+
+    pub fn process_watch(&mut self, obj: &ObjectLit) {
+        let mut watch_decls: Vec<WatchDecl> = vec![];
+        let mut passed_through: Vec<PropOrSpread> = vec![];
+        for entry in obj.props.iter() {
+            if let Some(problem) = self.watcher_problem(entry) {
+                self.diagnostics.push(problem);
+                passed_through.push(entry.clone());
+                continue;
+            }
+
+            // Each handler in an array is watched on its own
+            let prop = entry.as_prop().unwrap();
+            let (key, key_span) = utils::watch_key(prop).unwrap();
+            let source = self.watch_source(&key, key_span).unwrap();
+            watch_decls.extend(
+                utils::watch_handlers(prop)
+                    .unwrap()
+                    .into_iter()
+                    .map(|handler| WatchDecl {
+                        source: source.clone(),
+                        span: prop.span(),
+                        handler,
+                    }),
+            );
+        }
+        self.pass_through_entries("watch", obj.span, passed_through);
+
         // Add to component
-        if watch_decls.len() > 0 {
+        if !watch_decls.is_empty() {
             self.options.watch = Some(watch_decls);
         }
     }
 
     pub fn process_methods(&mut self, obj: &ObjectLit) {
         let mut methods: Vec<FnDecl> = vec![];
+        let mut passed_through: Vec<PropOrSpread> = vec![];
         for prop in obj.props.iter() {
-            if let PropOrSpread::Prop(boxed_expr) = prop {
-                if let Prop::Method(method_expr) = &**boxed_expr {
-                    if let PropName::Ident(ident) = &method_expr.key {
-                        methods.push(FnDecl {
-                            ident: ident.clone(),
-                            declare: false,
-                            function: method_expr.function.clone(),
-                        });
-                    }
+            match prop.as_prop().and_then(|prop| utils::method_decl(prop)) {
+                Some(decl) => methods.push(decl),
+                None => {
+                    self.diagnostics.push(
+                        Diagnostic::note(
+                            "option-passed-through",
+                            "method is not a function, it was copied to a `methods` option as is",
+                        )
+                        .with_span(prop.span()),
+                    );
+                    passed_through.push(prop.clone());
                 }
             }
         }
 
+        // Entries like `...mapActions([])` or `save: debounce(...)` are kept in a methods option
+        self.pass_through_entries("methods", obj.span, passed_through);
+
         // Add to component
-        if methods.len() > 0 {
+        if !methods.is_empty() {
             self.options.methods = Some(methods);
         }
     }
@@ -167,9 +329,18 @@ impl Visitor {
     pub fn process_key_value(&mut self, kv: &KeyValueProp) {
         if let Some(ident) = kv.key.as_ident() {
             match ident.sym.to_string().as_str() {
+                "name" => {
+                    self.options.name = Some(kv.value.clone());
+                }
                 "components" => {
                     self.options.components = Some(kv.value.clone());
                 }
+                "emits" => {
+                    self.options.emits = Some(kv.value.clone());
+                }
+                "inheritAttrs" => {
+                    self.options.inherit_attrs = Some(kv.value.clone());
+                }
                 "inject" => {
                     self.options.inject = Some(kv.value.clone());
                 }
@@ -191,18 +362,49 @@ impl Visitor {
                         self.process_methods(obj);
                     }
                 }
-                _ => {}
+                // data: function () {} or data: () => ({})
+                "data" => {
+                    self.options.data = utils::data_function(&kv.value);
+                }
+                // mounted: function () {}
+                name => {
+                    if let Expr::Fn(fn_expr) = &*kv.value {
+                        self.process_lifecycle_hook(name, &fn_expr.function);
+                    }
+                }
             }
         }
     }
 
     pub fn process_default_export(&mut self, object: &ObjectLit) {
         for prop in object.props.iter() {
-            if !prop.is_prop() {
+            // Keep what can't be converted, so no option is lost
+            if !utils::is_converted_option(prop) {
+                let message = match prop
+                    .as_prop()
+                    .and_then(|prop| utils::option_key_ident(prop))
+                {
+                    Some(key) => format!(
+                        "`{}` is not converted, it was copied to the component as is",
+                        key.sym
+                    ),
+                    None => {
+                        "this option is not converted, it was copied to the component as is".into()
+                    }
+                };
+                self.diagnostics.push(
+                    Diagnostic::note("option-passed-through", message).with_span(prop.span()),
+                );
+                self.options.passed_through.push(prop.clone());
                 continue;
             }
 
-            match &**prop.as_prop().unwrap() {
+            let prop = prop.as_prop().unwrap();
+            if let Some(ident) = utils::option_key_ident(prop) {
+                self.option_keys.push((ident.sym.to_string(), ident.span));
+            }
+
+            match &**prop {
                 Prop::Method(method_prop) => {
                     self.process_method_prop(method_prop);
                 }
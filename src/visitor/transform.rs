@@ -2,37 +2,117 @@ use itertools::Itertools;
 use std::collections::HashMap;
 
 use string_cache::Atom;
+use swc_common::comments::{Comments, SingleThreadedComments};
+use swc_common::{BytePos, Span, Spanned};
 use swc_ecma_ast::*;
 
 use super::{
-    utils::Ordered,
-    vue::{Inject, WatchDecl},
-    Visitor,
+    utils::{self, Ordered},
+    vue::{ComputedDecl, Inject, PropDecl, WatchCallback, WatchDecl, LIFECYCLE_HOOKS},
+    write, Visitor,
 };
+use crate::diagnostics::Diagnostic;
+use crate::error::OtcError;
+use crate::options::Target;
 
 impl Visitor {
-    pub fn transform_component(&mut self) {
-        // Pass through components
-        if let Some(components) = &self.options.components {
-            self.composition.components = Some(components.clone())
+    pub fn transform_component(&mut self) -> Result<(), OtcError> {
+        // The APIs found while rewriting `this` are imported after the ones the options become
+        let used_in_code = std::mem::take(&mut self.composition.imports);
+
+        // Script setup uses compiler macros rather than defineComponent
+        if !self.script_setup {
+            self.composition.use_api("defineComponent");
+        }
+        if self.composition.uses_instance {
+            self.composition.use_api("getCurrentInstance");
+        }
+        if self.script_setup {
+            let composables = self
+                .composition
+                .context_properties
+                .iter()
+                .filter_map(|property| write::context_composable(property))
+                .collect_vec();
+            for composable in composables {
+                self.composition.use_api(composable);
+            }
         }
 
-        // Pass through props
-        if let Some(props) = &self.options.props {
-            self.composition.props = Some(props.clone())
+        // Pass through the name, components, props, emits, inheritAttrs and unconverted options
+        self.composition.name = self.options.name.clone();
+        self.composition.components = self.options.components.clone();
+        self.composition.props = self.options.props.clone();
+        self.composition.emits = self.options.emits.clone();
+        self.composition.inherit_attrs = self.options.inherit_attrs.clone();
+        self.composition.passed_through = self.options.passed_through.clone();
+
+        // Declare props with a type if every prop has one
+        if self.typed_props {
+            if let Some(props) = &self.options.props {
+                self.composition.typed_props = type_props(props, &mut self.diagnostics);
+            }
+        }
+
+        // Script setup registers imported components by their binding name
+        if self.script_setup {
+            if let Some(components) = self.composition.components.take() {
+                self.diagnostics
+                    .extend(check_script_setup_components(&components));
+            }
+
+            // Without an emits option, defineEmits is built from the events passed to $emit
+            if self.composition.emits.is_none() {
+                for span in &self.dynamic_emits {
+                    self.diagnostics.push(
+                        Diagnostic::warning(
+                            "dynamic-emit",
+                            "emitted event is not a string literal, add it to defineEmits",
+                        )
+                        .with_span(*span),
+                    );
+                }
+            }
         }
 
         // Transform inject statements
         if let Some(injects) = &self.inject_set {
-            self.composition.inject_stmts = Some(transform_inject(injects));
+            let inject_stmts = transform_inject(injects);
+            if !inject_stmts.is_empty() {
+                self.composition.use_api("inject");
+            }
+            self.composition.inject_stmts = Some(inject_stmts);
         }
 
         // Transform data to refs
-        if let Some(func) = &self.options.data {
-            self.composition.ref_stmts = Some(transform_data(&func.body.as_ref().unwrap().stmts));
+        if let Some(body) = self
+            .options
+            .data
+            .as_ref()
+            .and_then(|func| func.body.as_ref())
+        {
+            let (mut data_stmts, ref_stmts) =
+                transform_data(&body.stmts, self.comments.as_ref(), &mut self.diagnostics)?;
+            if !ref_stmts.is_empty() {
+                self.composition.use_api("ref");
+            }
+            data_stmts.extend(ref_stmts);
+            self.composition.ref_stmts = Some(data_stmts);
+        }
+
+        // Declare the template refs, which are bound through the refs returned from setup
+        if !self.template_refs.is_empty() {
+            self.composition.use_api("ref");
+            self.composition.template_ref_stmts =
+                Some(transform_template_refs(&self.template_refs));
         }
 
-        // Transform created statements
+        // Inline beforeCreate and created, setup runs in their place
+        if let Some(before_create) = &self.options.before_create {
+            if let Some(block_stmt) = &before_create.body {
+                self.composition.before_create_stmts = Some(block_stmt.stmts.clone());
+            }
+        }
         if let Some(created) = &self.options.created {
             if let Some(block_stmt) = &created.body {
                 self.composition.created_stmts = Some(block_stmt.stmts.clone());
@@ -41,12 +121,20 @@ impl Visitor {
 
         // Transform computed
         if let Some(computed_decls) = &self.options.computed {
-            self.composition.computed = Some(transform_computed(computed_decls));
+            let computed = transform_computed(computed_decls, self.comments.as_ref());
+            if !computed.is_empty() {
+                self.composition.use_api("computed");
+            }
+            self.composition.computed = Some(computed);
         }
 
         // Transform watch
         if let Some(watch_decls) = &self.options.watch {
-            self.composition.watch = Some(transform_watch(watch_decls));
+            let watch = transform_watch(watch_decls, self.comments.as_ref());
+            if !watch.is_empty() {
+                self.composition.use_api("watch");
+            }
+            self.composition.watch = Some(watch);
         }
 
         // Transform methods
@@ -59,14 +147,236 @@ impl Visitor {
             );
         }
 
-        // Transform mounted
-        if let Some(mounted) = &self.options.mounted {
-            self.composition.mounted = Some(transform_mounted(mounted));
+        // Transform the other lifecycle hooks to their on* functions
+        for (hook, function) in &self.options.hooks {
+            if matches!(*hook, "beforeUnmount" | "unmounted") && self.target != Target::Vue3 {
+                self.diagnostics.push(
+                    Diagnostic::warning(
+                        "hook-not-in-target",
+                        format!(
+                            "Vue 2 never calls `{}`, it was converted anyway and will now run when the component is unmounted",
+                            hook
+                        ),
+                    )
+                    .with_span(function.span),
+                );
+            }
+
+            let (_, api) = LIFECYCLE_HOOKS
+                .iter()
+                .find(|(name, _)| name == hook)
+                .unwrap();
+            if let Some(stmt) = transform_hook(api, function) {
+                self.composition.use_api(api);
+                self.composition.hooks.push((hook, stmt));
+            }
+        }
+
+        for name in used_in_code {
+            self.composition.use_api(name);
+        }
+
+        self.carry_option_comments();
+        Ok(())
+    }
+
+    /**
+     * Comments written above an option key would otherwise be lost with the key. Move them onto the
+     * first statement generated from that option, or keep them on the key if it's passed through.
+     */
+    fn carry_option_comments(&mut self) {
+        let comments = match &self.comments {
+            Some(comments) => comments,
+            None => return,
+        };
+
+        for (key, span) in &self.option_keys {
+            if let Some((_, hook)) = self.composition.hooks.iter().find(|(hook, _)| hook == key) {
+                utils::move_leading_comments(comments, span.lo, hook.span().lo);
+                continue;
+            }
+
+            let stmts = match key.as_str() {
+                "inject" => &self.composition.inject_stmts,
+                "data" => &self.composition.ref_stmts,
+                "computed" => &self.composition.computed,
+                "watch" => &self.composition.watch,
+                "beforeCreate" => &self.composition.before_create_stmts,
+                "created" => &self.composition.created_stmts,
+                "methods" => &self.composition.method_decls,
+                "name" | "components" | "props" | "emits" | "inheritAttrs" => {
+                    self.composition.key_spans.insert(key.clone(), *span);
+                    continue;
+                }
+                _ => continue,
+            };
+
+            if let Some(first) = stmts.as_ref().and_then(|stmts| stmts.first()) {
+                utils::move_leading_comments(comments, span.lo, first.span().lo);
+            }
+        }
+
+        for stmt in self.composition.ref_stmts.iter_mut().flatten() {
+            carry_trailing_comments(comments, stmt);
+        }
+    }
+}
+
+/**
+ * A ref declared from a data property like `count: 0, // comment` spans the property, so its
+ * trailing comments would be written inside `ref(0)`. Move them after the declaration, which
+ * is extended over the comma after the property.
+ */
+fn carry_trailing_comments(comments: &SingleThreadedComments, stmt: &mut Stmt) {
+    let var = match stmt {
+        Stmt::Decl(Decl::Var(var)) if !var.span.is_dummy() => var,
+        _ => return,
+    };
+
+    let end = var.span.hi + BytePos(1);
+    let mut moved = comments.take_trailing(var.span.hi).unwrap_or_default();
+    moved.extend(comments.take_trailing(end).unwrap_or_default());
+    if !moved.is_empty() {
+        comments.add_trailing_comments(end, moved);
+        var.span.hi = end;
+    }
+}
+
+/**
+ * Read the props for a type-based declaration. If any prop has no type that can be written in
+ * TypeScript, the runtime declaration is kept instead.
+ */
+fn type_props(props: &Expr, diagnostics: &mut Vec<Diagnostic>) -> Option<Vec<PropDecl>> {
+    let decls = match utils::prop_decls_from_object_lit(props) {
+        Some(decls) => decls,
+        None => {
+            diagnostics.push(
+                Diagnostic::warning(
+                    "props-not-typed",
+                    "props can't be listed statically, kept the runtime declaration",
+                )
+                .with_span(props.span()),
+            );
+            return None;
         }
+    };
+
+    let untyped: Vec<&PropDecl> = decls.iter().filter(|decl| decl.ty.is_none()).collect();
+    for decl in &untyped {
+        diagnostics.push(
+            Diagnostic::warning(
+                "props-not-typed",
+                format!(
+                    "prop `{}` has no type that can be written in TypeScript, kept the runtime declaration",
+                    decl.name
+                ),
+            )
+            .with_span(decl.span),
+        );
+    }
+    if !untyped.is_empty() {
+        return None;
+    }
+
+    for decl in decls.iter().filter(|decl| decl.has_validator) {
+        diagnostics.push(
+            Diagnostic::warning(
+                "prop-validator-dropped",
+                format!(
+                    "the validator of prop `{}` can't be kept in a type",
+                    decl.name
+                ),
+            )
+            .with_span(decl.span),
+        );
     }
+
+    Some(decls)
+}
+
+/**
+ * Script setup only registers components under the name they're imported as, so warn about any
+ * component registered under another name or with an inline definition.
+ */
+fn check_script_setup_components(components: &Expr) -> Vec<Diagnostic> {
+    let obj = match components {
+        Expr::Object(obj) => obj,
+        _ => {
+            return vec![Diagnostic::warning(
+                "component-not-registered",
+                "components are not an object literal, import them for <script setup>",
+            )
+            .with_span(components.span())]
+        }
+    };
+
+    obj.props
+        .iter()
+        .filter_map(|prop| match prop.as_prop().map(|prop| &**prop) {
+            Some(Prop::Shorthand(_)) => None,
+            Some(Prop::KeyValue(kv))
+                if utils::ident_from_prop_name(&kv.key)
+                    .zip(kv.value.as_ident())
+                    .is_some_and(|(key, value)| key.sym == value.sym) =>
+            {
+                None
+            }
+            _ => Some(
+                Diagnostic::warning(
+                    "component-not-registered",
+                    "component is not imported under its registered name, import it as that name for <script setup>",
+                )
+                .with_span(prop.span()),
+            ),
+        })
+        .collect()
+}
+
+/** Whether a single `return` can be collapsed into an arrow expression body without losing comments */
+fn can_collapse_return(ret: &ReturnStmt, comments: Option<&SingleThreadedComments>) -> bool {
+    comments.is_none_or(|comments| {
+        !comments.has_leading(ret.span.lo) && !comments.has_trailing(ret.span.hi)
+    })
 }
 
-pub fn transform_computed(fn_decls: &Vec<FnDecl>) -> Vec<Stmt> {
+/**
+ * An arrow function with the body of an option function, collapsing a lone `return` into an
+ * expression body
+ */
+fn option_arrow(
+    function: &Function,
+    params: Vec<Pat>,
+    comments: Option<&SingleThreadedComments>,
+) -> Option<Expr> {
+    let body = function.body.as_ref()?;
+    let body = match body.stmts.as_slice() {
+        [Stmt::Return(ret @ ReturnStmt { arg: Some(arg), .. })]
+            if can_collapse_return(ret, comments) =>
+        {
+            BlockStmtOrExpr::Expr(arg.clone())
+        }
+        _ => BlockStmtOrExpr::BlockStmt(body.clone()),
+    };
+
+    Some(Expr::Arrow(ArrowExpr {
+        span: Default::default(),
+        is_async: function.is_async,
+        is_generator: function.is_generator,
+        type_params: None,
+        return_type: function.return_type.clone(),
+        params,
+        body,
+    }))
+}
+
+/**
+ * `const x = computed(() => ...)`, or `computed({ get: () => ..., set: (v) => {} })` if there's
+ * a setter. The getter's parameter, the instance, isn't needed anymore.
+ */
+pub fn transform_computed(
+    decls: &[ComputedDecl],
+    comments: Option<&SingleThreadedComments>,
+) -> Vec<Stmt> {
     let computed_callee = Callee::Expr(Box::new(Expr::Ident(Ident {
         optional: false,
         span: Default::default(),
@@ -74,27 +384,32 @@ pub fn transform_computed(fn_decls: &Vec<FnDecl>) -> Vec<Stmt> {
     })));
 
     // Map and return
-    fn_decls
+    decls
         .iter()
         .filter_map(|decl| {
-            if decl.function.body.is_none() {
-                return None;
-            }
-
-            // Optimize return statement if possible
-            let body = decl.function.body.as_ref().unwrap();
-            let mut arrow_expr_body = BlockStmtOrExpr::BlockStmt(body.clone());
-            {
-                let stmts = &arrow_expr_body.as_block_stmt().unwrap().stmts;
-                if stmts.len() == 1 {
-                    if let Stmt::Return(r_stmt) = &stmts[0] {
-                        arrow_expr_body = BlockStmtOrExpr::Expr(r_stmt.arg.clone().unwrap())
-                    }
+            let get = option_arrow(&decl.get, vec![], comments)?;
+            let arg = match &decl.set {
+                Some(set) => {
+                    let params = set.params.iter().map(|param| param.pat.clone()).collect();
+                    let accessor = |name: &str, function: Expr| {
+                        PropOrSpread::Prop(Box::new(Prop::KeyValue(KeyValueProp {
+                            key: PropName::Ident(utils::ident(name)),
+                            value: Box::new(function),
+                        })))
+                    };
+                    Expr::Object(ObjectLit {
+                        span: Default::default(),
+                        props: vec![
+                            accessor("get", get),
+                            accessor("set", option_arrow(set, params, comments)?),
+                        ],
+                    })
                 }
-            }
+                None => get,
+            };
 
             Some(Stmt::Decl(Decl::Var(VarDecl {
-                span: Default::default(),
+                span: decl.span,
                 declare: false,
                 kind: VarDeclKind::Const,
                 decls: vec![VarDeclarator {
@@ -110,15 +425,7 @@ pub fn transform_computed(fn_decls: &Vec<FnDecl>) -> Vec<Stmt> {
                         type_args: None,
                         args: vec![ExprOrSpread {
                             spread: None,
-                            expr: Box::new(Expr::Arrow(ArrowExpr {
-                                span: Default::default(),
-                                is_async: false,
-                                is_generator: false,
-                                type_params: None,
-                                return_type: None,
-                                params: vec![],
-                                body: arrow_expr_body,
-                            })),
+                            expr: Box::new(arg),
                         }],
                     }))),
                 }],
@@ -127,7 +434,10 @@ pub fn transform_computed(fn_decls: &Vec<FnDecl>) -> Vec<Stmt> {
         .collect()
 }
 
-pub fn transform_watch(watch_decls: &Vec<WatchDecl>) -> Vec<Stmt> {
+pub fn transform_watch(
+    watch_decls: &[WatchDecl],
+    comments: Option<&SingleThreadedComments>,
+) -> Vec<Stmt> {
     let computed_callee = Callee::Expr(Box::new(Expr::Ident(Ident {
         optional: false,
         span: Default::default(),
@@ -138,47 +448,30 @@ pub fn transform_watch(watch_decls: &Vec<WatchDecl>) -> Vec<Stmt> {
     watch_decls
         .iter()
         .filter_map(|decl| {
-            if decl.function.body.is_none() {
-                return None;
-            }
-
-            // Optimize return statement if possible
-            let body = decl.function.body.as_ref().unwrap();
-            let mut arrow_expr_body = BlockStmtOrExpr::BlockStmt(body.clone());
-            {
-                let stmts = &arrow_expr_body.as_block_stmt().unwrap().stmts;
-                if stmts.len() == 1 {
-                    if let Stmt::Return(r_stmt) = &stmts[0] {
-                        arrow_expr_body = BlockStmtOrExpr::Expr(r_stmt.arg.clone().unwrap())
-                    }
+            // The handler keeps its parameters, the new and old values
+            let callback = match &decl.handler.callback {
+                WatchCallback::Function(function) => {
+                    let params = function.params.iter().map(|p| p.pat.clone()).collect();
+                    option_arrow(function, params, comments)?
                 }
-            }
-
-            // Create params/args
-            let params = decl.function.params.iter().map(|p| p.pat.clone()).collect();
+                WatchCallback::Method(name) => Expr::Ident(name.clone()),
+            };
             let mut args = vec![
                 ExprOrSpread {
                     spread: None,
-                    expr: Box::new(Expr::Ident(decl.ident.clone())),
+                    expr: decl.source.clone(),
                 },
                 ExprOrSpread {
                     spread: None,
-                    expr: Box::new(Expr::Arrow(ArrowExpr {
-                        span: Default::default(),
-                        is_async: false,
-                        is_generator: false,
-                        type_params: None,
-                        return_type: None,
-                        params,
-                        body: arrow_expr_body,
-                    })),
+                    expr: Box::new(callback),
                 },
             ];
 
             // Inject deep/immediatge if needed
-            if decl.deep.is_some() || decl.immediate.is_some() {
+            let handler = &decl.handler;
+            if handler.deep.is_some() || handler.immediate.is_some() {
                 let mut props = vec![];
-                if let Some(deep) = &decl.deep {
+                if let Some(deep) = &handler.deep {
                     props.push(PropOrSpread::Prop(Box::new(Prop::KeyValue(KeyValueProp {
                         key: PropName::Ident(Ident {
                             optional: false,
@@ -188,7 +481,7 @@ pub fn transform_watch(watch_decls: &Vec<WatchDecl>) -> Vec<Stmt> {
                         value: deep.clone(),
                     }))));
                 }
-                if let Some(immediate) = &decl.immediate {
+                if let Some(immediate) = &handler.immediate {
                     props.push(PropOrSpread::Prop(Box::new(Prop::KeyValue(KeyValueProp {
                         key: PropName::Ident(Ident {
                             optional: false,
@@ -209,10 +502,12 @@ pub fn transform_watch(watch_decls: &Vec<WatchDecl>) -> Vec<Stmt> {
                 });
             }
 
+            // The call carries the span too, since that's where leading comments are emitted
+            let span = decl.span;
             Some(Stmt::Expr(ExprStmt {
-                span: Default::default(),
+                span,
                 expr: Box::new(Expr::Call(CallExpr {
-                    span: Default::default(),
+                    span,
                     callee: computed_callee.clone(),
                     type_args: None,
                     args,
@@ -222,37 +517,34 @@ pub fn transform_watch(watch_decls: &Vec<WatchDecl>) -> Vec<Stmt> {
         .collect()
 }
 
-pub fn transform_mounted(mounted: &Function) -> Vec<Stmt> {
-    if mounted.body.is_none() {
-        return vec![];
-    }
-
-    // Return vec with single onMounted wrawpper
-    let body = mounted.body.as_ref().unwrap();
-    vec![Stmt::Expr(ExprStmt {
-        span: Default::default(),
+/** Wrap a lifecycle hook in its `on*` function, e.g. `onMounted(() => {})` */
+pub fn transform_hook(api: &str, hook: &Function) -> Option<Stmt> {
+    let body = hook.body.as_ref()?;
+    Some(Stmt::Expr(ExprStmt {
+        span: hook.span,
         expr: Box::new(Expr::Call(CallExpr {
-            span: Default::default(),
+            span: hook.span,
             type_args: None,
             callee: Callee::Expr(Box::new(Expr::Ident(Ident {
                 optional: false,
                 span: Default::default(),
-                sym: Atom::from("onMounted"),
+                sym: Atom::from(api),
             }))),
             args: vec![ExprOrSpread {
                 spread: None,
                 expr: Box::new(Expr::Arrow(ArrowExpr {
                     span: Default::default(),
-                    is_async: mounted.is_async,
-                    is_generator: mounted.is_generator,
+                    is_async: hook.is_async,
+                    is_generator: hook.is_generator,
                     type_params: None,
                     return_type: None,
-                    params: vec![],
+                    // errorCaptured receives the error, the component and where it came from
+                    params: hook.params.iter().map(|param| param.pat.clone()).collect(),
                     body: BlockStmtOrExpr::BlockStmt(body.clone()),
                 })),
             }],
         })),
-    })]
+    }))
 }
 
 pub fn transform_inject(injects: &HashMap<String, Ordered<Inject>>) -> Vec<Stmt> {
@@ -262,7 +554,7 @@ pub fn transform_inject(injects: &HashMap<String, Ordered<Inject>>) -> Vec<Stmt>
         sym: Atom::from("inject"),
     })));
 
-    return injects
+    injects
         .iter()
         // Sort in order discovered
         .sorted_by(|a, b| Ord::cmp(&a.1.order, &b.1.order))
@@ -280,7 +572,7 @@ pub fn transform_inject(injects: &HashMap<String, Ordered<Inject>>) -> Vec<Stmt>
             }
 
             Stmt::Decl(Decl::Var(VarDecl {
-                span: Default::default(),
+                span: inj.span,
                 declare: false,
                 kind: VarDeclKind::Const,
                 decls: vec![VarDeclarator {
@@ -303,23 +595,70 @@ pub fn transform_inject(injects: &HashMap<String, Ordered<Inject>>) -> Vec<Stmt>
                 }],
             }))
         })
-        .collect();
+        .collect()
 }
 
-pub fn transform_data(stmts: &Vec<Stmt>) -> Vec<Stmt> {
-    let return_expr = stmts
+/** `const foo = ref(null);` for each template ref */
+pub fn transform_template_refs(template_refs: &[(String, Ident)]) -> Vec<Stmt> {
+    template_refs
         .iter()
-        .find(|stmt| stmt.is_return_stmt() && stmt.as_return_stmt().unwrap().arg.is_some())
-        .expect("Data return statement not found!")
-        .as_return_stmt()
-        .unwrap()
-        .arg
-        .as_ref()
-        .unwrap();
+        .map(|(_, name)| {
+            Stmt::Decl(Decl::Var(VarDecl {
+                kind: VarDeclKind::Const,
+                span: Default::default(),
+                declare: false,
+                decls: vec![VarDeclarator {
+                    definite: false,
+                    span: Default::default(),
+                    name: Pat::Ident(BindingIdent {
+                        id: name.clone(),
+                        type_ann: None,
+                    }),
+                    init: Some(Box::new(Expr::Call(CallExpr {
+                        span: Default::default(),
+                        type_args: None,
+                        callee: Callee::Expr(Box::new(Expr::Ident(utils::ident("ref")))),
+                        args: vec![ExprOrSpread {
+                            spread: None,
+                            expr: Box::new(Expr::Lit(Lit::Null(Null {
+                                span: Default::default(),
+                            }))),
+                        }],
+                    }))),
+                }],
+            }))
+        })
+        .collect()
+}
+
+/**
+ * The statements of setup for the body of `data`: the statements before its `return`, which are
+ * carried over as they are, and a ref for each property of the returned object
+ */
+pub fn transform_data(
+    stmts: &[Stmt],
+    comments: Option<&SingleThreadedComments>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<(Vec<Stmt>, Vec<Stmt>), OtcError> {
+    let (index, return_stmt, return_expr) = stmts
+        .iter()
+        .enumerate()
+        .find_map(|(index, stmt)| match stmt {
+            Stmt::Return(ret @ ReturnStmt { arg: Some(arg), .. }) => Some((index, ret, arg)),
+            _ => None,
+        })
+        .ok_or_else(|| OtcError::Unsupported("data() has no return statement".into()))?;
+
+    let preceding = &stmts[..index];
+    if utils::has_return(preceding) {
+        return Err(OtcError::Unsupported(
+            "data() returns from more than one place".into(),
+        ));
+    }
 
     let props = &return_expr
         .as_object()
-        .expect("Data return expr is not an object!")
+        .ok_or_else(|| OtcError::Unsupported("data() does not return an object literal".into()))?
         .props;
     let ref_callee = Callee::Expr(Box::new(Expr::Ident(Ident {
         optional: false,
@@ -328,12 +667,25 @@ pub fn transform_data(stmts: &Vec<Stmt>) -> Vec<Stmt> {
     })));
 
     // Create new setup statements
+    let mut locals = utils::block_scope_names(preceding, false);
+    locals.extend(utils::function_scope_names(
+        [],
+        Some(&BlockStmt {
+            span: Default::default(),
+            stmts: preceding.to_vec(),
+        }),
+        false,
+    ));
     let mut setup_statements: Vec<Stmt> = Vec::new();
     for item in props.iter() {
-        let prop = item.as_prop().unwrap();
-        let maybe_ref_value: Option<(Ident, CallExpr)> = match &**prop {
+        let prop = item.as_prop().ok_or_else(|| {
+            OtcError::Unsupported("spread in the object returned from data()".into())
+        })?;
+        let maybe_ref_value: Option<(Ident, CallExpr, Span)> = match &**prop {
             Prop::KeyValue(kv) => Some((
-                kv.key.as_ident().unwrap().clone(),
+                utils::ident_from_prop_name(&kv.key).ok_or_else(|| {
+                    OtcError::Unsupported("computed key in the object returned from data()".into())
+                })?,
                 CallExpr {
                     span: Default::default(),
                     type_args: None,
@@ -343,6 +695,7 @@ pub fn transform_data(stmts: &Vec<Stmt>) -> Vec<Stmt> {
                         expr: kv.value.clone(),
                     }],
                 },
+                kv.key.span().to(kv.value.span()),
             )),
             // TODO: Handle shorthands
             // Prop::Shorthand(_shorthand) => {
@@ -364,17 +717,39 @@ pub fn transform_data(stmts: &Vec<Stmt>) -> Vec<Stmt> {
         };
 
         // Skip if not valid
-        if maybe_ref_value.is_none() {
-            continue;
-        }
-
         // ID is the var name, call_expr is the call to ref that wraps the expression
-        let (id, call_expr) = maybe_ref_value.unwrap();
+        let (id, call_expr, span) = match maybe_ref_value {
+            Some(value) => value,
+            None => {
+                diagnostics.push(
+                    Diagnostic::warning(
+                        "data-property-skipped",
+                        "property returned from data() was not converted to a ref",
+                    )
+                    .with_span(prop.span()),
+                );
+                continue;
+            }
+        };
+
+        // Setup has both the locals of data and the refs
+        if locals.iter().any(|local| *local == *id.sym) {
+            diagnostics.push(
+                Diagnostic::error(
+                    "data-local-collision",
+                    format!(
+                        "`{}` is declared in data() as well as returned from it, rename the local",
+                        id.sym
+                    ),
+                )
+                .with_span(span),
+            );
+        }
 
         // Push setup statement into statements
         setup_statements.push(Stmt::Decl(Decl::Var(VarDecl {
             kind: swc_ecma_ast::VarDeclKind::Const,
-            span: Default::default(),
+            span,
             declare: Default::default(),
             decls: vec![VarDeclarator {
                 definite: false,
@@ -385,5 +760,10 @@ pub fn transform_data(stmts: &Vec<Stmt>) -> Vec<Stmt> {
         })));
     }
 
-    setup_statements
+    // Comments on the return, like the one introducing the state, lead the first ref instead
+    if let (Some(comments), Some(first)) = (comments, setup_statements.first()) {
+        utils::move_leading_comments(comments, return_stmt.span.lo, first.span().lo);
+    }
+
+    Ok((preceding.to_vec(), setup_statements))
 }
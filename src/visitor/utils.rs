@@ -3,10 +3,13 @@ use std::{
     iter::FromIterator,
 };
 
-use super::vue::Inject;
+use super::vue::{ComputedDecl, Inject, PropDecl, WatchCallback, WatchHandler, LIFECYCLE_HOOKS};
 
 use string_cache::Atom;
+use swc_common::comments::{Comments, SingleThreadedComments};
+use swc_common::{BytePos, Span, Spanned};
 use swc_ecma_ast::*;
+use swc_ecma_visit::{Visit, VisitWith};
 
 /** Represents some structure that may want to be ordered */
 #[derive(Debug)]
@@ -15,39 +18,433 @@ pub struct Ordered<T> {
     pub value: T,
 }
 
+/** Return an identifier for a prop name, if it's an identifier or a string that is a valid identifier */
+pub fn ident_from_prop_name(key: &PropName) -> Option<Ident> {
+    match key {
+        PropName::Ident(id) => Some(id.clone()),
+        PropName::Str(s) if Ident::verify_symbol(&s.value).is_ok() => Some(Ident {
+            optional: false,
+            span: s.span,
+            sym: s.value.clone(),
+        }),
+        _ => None,
+    }
+}
+
+/**
+ * Return the options object of a default export, either a plain object or one wrapped in
+ * `defineComponent(...)` or `Vue.extend(...)`. Components that already have a `setup` function
+ * are left alone.
+ */
+pub fn component_object(expr: &Expr) -> Option<&ObjectLit> {
+    let obj = match expr {
+        Expr::Object(obj) => obj,
+        Expr::Paren(ParenExpr { expr, .. }) | Expr::TsAs(TsAsExpr { expr, .. }) => {
+            return component_object(expr)
+        }
+        Expr::Call(CallExpr {
+            callee: Callee::Expr(callee),
+            args,
+            ..
+        }) if is_component_wrapper(callee) => match args.as_slice() {
+            [ExprOrSpread { spread: None, expr }] => expr.as_object()?,
+            _ => return None,
+        },
+        _ => return None,
+    };
+
+    let has_setup = obj.props.iter().any(|prop| {
+        prop.as_prop()
+            .and_then(|prop| option_key_ident(prop))
+            .is_some_and(|ident| &*ident.sym == "setup")
+    });
+    (!has_setup).then_some(obj)
+}
+
+/** Whether a callee is `defineComponent` or `Vue.extend` */
+fn is_component_wrapper(callee: &Expr) -> bool {
+    match callee {
+        Expr::Ident(ident) => &*ident.sym == "defineComponent",
+        Expr::Member(MemberExpr {
+            obj,
+            prop: MemberProp::Ident(prop),
+            ..
+        }) => obj.as_ident().is_some_and(|obj| &*obj.sym == "Vue") && &*prop.sym == "extend",
+        _ => false,
+    }
+}
+
+/** Whether a module item exports a value by name, which `<script setup>` doesn't allow. Types are fine */
+pub fn is_value_export(item: &ModuleItem) -> bool {
+    match item {
+        ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(export)) => {
+            !matches!(export.decl, Decl::TsInterface(_) | Decl::TsTypeAlias(_))
+        }
+        ModuleItem::ModuleDecl(ModuleDecl::ExportNamed(export)) => !export.type_only,
+        ModuleItem::ModuleDecl(ModuleDecl::ExportAll(_)) => true,
+        _ => false,
+    }
+}
+
+pub fn ident(sym: &str) -> Ident {
+    Ident {
+        optional: false,
+        span: Default::default(),
+        sym: Atom::from(sym),
+    }
+}
+
+/** `obj.prop` */
+pub fn member_expr(obj: impl Into<Expr>, prop: Ident) -> Expr {
+    Expr::Member(MemberExpr {
+        span: Default::default(),
+        obj: Box::new(obj.into()),
+        prop: MemberProp::Ident(prop),
+    })
+}
+
+/** The name of the member if `expr` is `this.name` */
+pub fn this_member_name(expr: &Expr) -> Option<Ident> {
+    match expr {
+        Expr::Member(MemberExpr {
+            obj,
+            prop: MemberProp::Ident(prop),
+            ..
+        }) if obj.is_this() => Some(prop.clone()),
+        _ => None,
+    }
+}
+
+/** Return the object and property of a member expression on an identifier, e.g. `self` and `x` for `self.x` */
+pub fn ident_member(expr: &Expr) -> Option<(&Ident, &Ident)> {
+    match expr {
+        Expr::Member(MemberExpr {
+            obj,
+            prop: MemberProp::Ident(prop),
+            ..
+        }) => Some((obj.as_ident()?, prop)),
+        _ => None,
+    }
+}
+
+/** Return the key of a component option, e.g. `data` for `data() {}` or `props: {}` */
+pub fn option_key_ident(prop: &Prop) -> Option<&Ident> {
+    match prop {
+        Prop::Method(method) => method.key.as_ident(),
+        Prop::KeyValue(kv) => kv.key.as_ident(),
+        _ => None,
+    }
+}
+
+/**
+ * Whether a component option is converted. Anything else, like `mixins` or spreads, is passed
+ * through to the converted component as is.
+ */
+pub fn is_converted_option(option: &PropOrSpread) -> bool {
+    let prop = match option {
+        PropOrSpread::Prop(prop) => prop,
+        PropOrSpread::Spread(_) => return false,
+    };
+    let is_hook = |name: &str| {
+        matches!(name, "beforeCreate" | "created")
+            || LIFECYCLE_HOOKS.iter().any(|(hook, _)| *hook == name)
+    };
+
+    match &**prop {
+        Prop::Method(method) => method
+            .key
+            .as_ident()
+            .is_some_and(|key| &*key.sym == "data" || is_hook(&key.sym)),
+        Prop::KeyValue(kv) => kv.key.as_ident().is_some_and(|key| match &*key.sym {
+            "name" | "components" | "props" | "emits" | "inheritAttrs" | "inject" => true,
+            "computed" | "watch" | "methods" => kv.value.is_object(),
+            "data" => data_function(&kv.value).is_some(),
+            name => is_hook(name) && kv.value.is_fn_expr(),
+        }),
+        _ => false,
+    }
+}
+
+/**
+ * Whether a component option is converted into code in setup, rather than kept as an option like
+ * `name` or `props`. A component without any would only gain an empty setup.
+ */
+pub fn is_setup_option(option: &PropOrSpread) -> bool {
+    let kept = match option {
+        PropOrSpread::Prop(prop) => option_key_ident(prop).is_some_and(|key| {
+            matches!(
+                &*key.sym,
+                "name" | "components" | "props" | "emits" | "inheritAttrs"
+            )
+        }),
+        PropOrSpread::Spread(_) => false,
+    };
+    !kept && is_converted_option(option)
+}
+
+/** The function of a getter or setter, written as a method, a function or an arrow function */
+fn accessor_function(prop: &Prop) -> Option<Function> {
+    match prop {
+        Prop::Method(method) => Some(method.function.clone()),
+        Prop::KeyValue(kv) => match &*kv.value {
+            Expr::Fn(fn_expr) => Some(fn_expr.function.clone()),
+            Expr::Arrow(arrow) => Some(arrow_to_function(arrow)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/** The function of a `data` option written as `data: function () {}` or `data: () => ({})` */
+pub fn data_function(value: &Expr) -> Option<Function> {
+    match value {
+        Expr::Fn(fn_expr) => Some(fn_expr.function.clone()),
+        Expr::Arrow(arrow) => {
+            let mut arrow = arrow.clone();
+            if let BlockStmtOrExpr::Expr(body) = &mut arrow.body {
+                while let Expr::Paren(paren) = &**body {
+                    *body = paren.expr.clone();
+                }
+            }
+            Some(arrow_to_function(&arrow))
+        }
+        _ => None,
+    }
+}
+
+/** Read an entry of methods, written as a method, `save: function () {}` or `save: () => ...` */
+pub fn method_decl(prop: &Prop) -> Option<FnDecl> {
+    let ident = match prop {
+        Prop::Method(method) => ident_from_prop_name(&method.key)?,
+        Prop::KeyValue(kv) => ident_from_prop_name(&kv.key)?,
+        _ => return None,
+    };
+
+    Some(FnDecl {
+        ident,
+        declare: false,
+        function: accessor_function(prop)?,
+    })
+}
+
+/**
+ * Read an entry of computed, which is either a getter, `double() {}`, `double: function () {}`
+ * or `double: (vm) => ...`, or an object with a getter and a setter, `double: { get() {}, set(v) {} }`
+ */
+pub fn computed_decl(prop: &Prop) -> Option<ComputedDecl> {
+    let ident = match prop {
+        Prop::Method(method) => ident_from_prop_name(&method.key)?,
+        Prop::KeyValue(kv) => ident_from_prop_name(&kv.key)?,
+        _ => return None,
+    };
+
+    let (get, set) = match prop {
+        Prop::KeyValue(KeyValueProp { value, .. }) if value.is_object() => {
+            let accessor = |name: &str| {
+                value.as_object().unwrap().props.iter().find_map(|prop| {
+                    let prop = prop.as_prop()?;
+                    option_key_ident(prop)
+                        .filter(|key| &*key.sym == name)
+                        .and_then(|_| accessor_function(prop))
+                })
+            };
+            (accessor("get")?, accessor("set"))
+        }
+        prop => (accessor_function(prop)?, None),
+    };
+
+    Some(ComputedDecl {
+        ident,
+        span: prop.span(),
+        get,
+        set,
+    })
+}
+
+/** The key of a watcher, which may be a path like `'form.name'`, and its span */
+pub fn watch_key(prop: &Prop) -> Option<(String, Span)> {
+    let key = match prop {
+        Prop::Method(method) => &method.key,
+        Prop::KeyValue(kv) => &kv.key,
+        _ => return None,
+    };
+
+    match key {
+        PropName::Ident(ident) => Some((ident.sym.to_string(), ident.span)),
+        PropName::Str(str) => Some((str.value.to_string(), str.span)),
+        _ => None,
+    }
+}
+
+/** A watch callback, which is a method name, a function or an arrow function */
+fn watch_callback(value: &Expr) -> Option<WatchCallback> {
+    match value {
+        Expr::Lit(Lit::Str(name)) if Ident::verify_symbol(&name.value).is_ok() => {
+            Some(WatchCallback::Method(Ident {
+                span: name.span,
+                ..ident(&name.value)
+            }))
+        }
+        Expr::Fn(fn_expr) => Some(WatchCallback::Function(fn_expr.function.clone())),
+        Expr::Arrow(arrow) => Some(WatchCallback::Function(arrow_to_function(arrow))),
+        _ => None,
+    }
+}
+
+/** A watch handler, either a callback or an object like `{ handler() {}, deep: true }` */
+fn watch_handler(value: &Expr) -> Option<WatchHandler> {
+    let options = match value {
+        Expr::Object(options) => options,
+        callback => {
+            return Some(WatchHandler {
+                callback: watch_callback(callback)?,
+                deep: None,
+                immediate: None,
+            })
+        }
+    };
+
+    let option = |name: &str| {
+        options.props.iter().find_map(|prop| {
+            let prop = prop.as_prop()?;
+            option_key_ident(prop)
+                .filter(|key| &*key.sym == name)
+                .map(|_| &**prop)
+        })
+    };
+    let value = |name: &str| match option(name)? {
+        Prop::KeyValue(kv) => Some(kv.value.clone()),
+        _ => None,
+    };
+
+    Some(WatchHandler {
+        callback: match option("handler")? {
+            Prop::Method(method) => WatchCallback::Function(method.function.clone()),
+            Prop::KeyValue(kv) => watch_callback(&kv.value)?,
+            _ => return None,
+        },
+        deep: value("deep"),
+        immediate: value("immediate"),
+    })
+}
+
+/**
+ * Read the handlers of a watcher, which is a method, a handler, or an array of handlers that are
+ * each watched on their own. Returns `None` if any of them can't be converted.
+ */
+pub fn watch_handlers(prop: &Prop) -> Option<Vec<WatchHandler>> {
+    match prop {
+        Prop::Method(method) => Some(vec![WatchHandler {
+            callback: WatchCallback::Function(method.function.clone()),
+            deep: None,
+            immediate: None,
+        }]),
+        Prop::KeyValue(kv) => match &*kv.value {
+            Expr::Array(handlers) => handlers
+                .elems
+                .iter()
+                .map(|elem| match elem {
+                    Some(ExprOrSpread { spread: None, expr }) => watch_handler(expr),
+                    _ => None,
+                })
+                .collect(),
+            handler => Some(vec![watch_handler(handler)?]),
+        },
+        _ => None,
+    }
+}
+
+/** The first parameter of an option function, e.g. `vm` for `double: (vm) => vm.count * 2` */
+pub fn first_param_ident(option: &Prop) -> Option<&Ident> {
+    let pat = match option {
+        Prop::Method(method) => &method.function.params.first()?.pat,
+        Prop::KeyValue(kv) => match &*kv.value {
+            Expr::Fn(fn_expr) => &fn_expr.function.params.first()?.pat,
+            Expr::Arrow(arrow) => arrow.params.first()?,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    pat.as_ident().map(|binding| &binding.id)
+}
+
+/** A function with the parameters and body of an arrow function, `return`ing an expression body */
+pub fn arrow_to_function(arrow: &ArrowExpr) -> Function {
+    let body = match &arrow.body {
+        BlockStmtOrExpr::BlockStmt(block) => block.clone(),
+        BlockStmtOrExpr::Expr(expr) => BlockStmt {
+            span: expr.span(),
+            stmts: vec![Stmt::Return(ReturnStmt {
+                span: expr.span(),
+                arg: Some(expr.clone()),
+            })],
+        },
+    };
+
+    Function {
+        params: arrow
+            .params
+            .iter()
+            .map(|pat| Param {
+                span: pat.span(),
+                decorators: vec![],
+                pat: pat.clone(),
+            })
+            .collect(),
+        decorators: vec![],
+        span: arrow.span,
+        body: Some(body),
+        is_generator: arrow.is_generator,
+        is_async: arrow.is_async,
+        type_params: arrow.type_params.clone(),
+        return_type: arrow.return_type.clone(),
+    }
+}
+
+/** Move the comments leading `from` so they lead `to`, ahead of any comments already there */
+pub fn move_leading_comments(comments: &SingleThreadedComments, from: BytePos, to: BytePos) {
+    if from == to || from.is_dummy() || to.is_dummy() {
+        return;
+    }
+
+    if let Some(mut moved) = comments.take_leading(from) {
+        moved.extend(comments.take_leading(to).unwrap_or_default());
+        comments.add_leading_comments(to, moved);
+    }
+}
+
 /** Return the set of injections from an object lit */
-pub fn inject_set_from_object_lit(expr: &Box<Expr>) -> Option<HashMap<String, Ordered<Inject>>> {
+pub fn inject_set_from_object_lit(expr: &Expr) -> Option<HashMap<String, Ordered<Inject>>> {
     let mut values: Vec<Ordered<Inject>> = vec![];
 
     // Handle array literal of string literal
-    if let Expr::Array(arr) = &**expr {
+    if let Expr::Array(arr) = expr {
         values = arr
             .elems
             .iter()
             .enumerate()
             .filter_map(|(index, elem)| {
                 if let Some(expr_or_spread) = elem {
-                    if let Expr::Lit(lit) = &*expr_or_spread.expr {
-                        if let Lit::Str(string_lit) = lit {
-                            return Some(Ordered {
-                                order: index,
-                                value: Inject {
-                                    name: string_lit.value.to_string(),
-                                    from: expr_or_spread.expr.clone(),
-                                    default: None,
-                                },
-                            });
-                        }
+                    if let Expr::Lit(Lit::Str(string_lit)) = &*expr_or_spread.expr {
+                        return Some(Ordered {
+                            order: index,
+                            value: Inject {
+                                name: string_lit.value.to_string(),
+                                span: string_lit.span,
+                                from: expr_or_spread.expr.clone(),
+                                default: None,
+                            },
+                        });
                     }
                 }
 
-                return None;
+                None
             })
             .collect();
     }
 
     // Handle object literal (renaming / defaults)
-    if let Expr::Object(obj) = &**expr {
+    if let Expr::Object(obj) = expr {
         values = obj
             .props
             .iter()
@@ -63,13 +460,14 @@ pub fn inject_set_from_object_lit(expr: &Box<Expr>) -> Option<HashMap<String, Or
                 }
 
                 // Get kv, key must be ident
-                let KeyValueProp { key, value } = &*prop.as_key_value().unwrap();
+                let KeyValueProp { key, value } = prop.as_key_value().unwrap();
                 if !key.is_ident() {
                     return None;
                 }
 
                 // Extract injection name
                 let name = key.as_ident().unwrap().sym.to_string();
+                let span = key.span().to(value.span());
 
                 // If not object lit, just pass along expression
                 if !value.is_object() {
@@ -77,6 +475,7 @@ pub fn inject_set_from_object_lit(expr: &Box<Expr>) -> Option<HashMap<String, Or
                         order: index,
                         value: Inject {
                             name,
+                            span,
                             from: value.clone(),
                             default: None,
                         },
@@ -136,19 +535,20 @@ pub fn inject_set_from_object_lit(expr: &Box<Expr>) -> Option<HashMap<String, Or
                         order: index,
                         value: Inject {
                             name,
+                            span,
                             from,
                             default,
                         },
                     });
                 }
 
-                return None;
+                None
             })
             .collect();
     }
 
     // Return if found
-    if values.len() > 0 {
+    if !values.is_empty() {
         let mut map = HashMap::new();
         for inject in values.into_iter() {
             map.insert(inject.value.name.clone(), inject);
@@ -157,14 +557,51 @@ pub fn inject_set_from_object_lit(expr: &Box<Expr>) -> Option<HashMap<String, Or
         return Some(map);
     }
 
-    return None;
+    None
 }
 
-pub fn prop_set_from_object_lit(expr: &Box<Expr>) -> Option<HashSet<String>> {
+/** The keys of an object literal, e.g. the names of the methods in `methods: { ... }` */
+pub fn object_keys(expr: &Expr) -> Vec<String> {
+    let obj = match expr {
+        Expr::Object(obj) => obj,
+        _ => return vec![],
+    };
+
+    obj.props
+        .iter()
+        .filter_map(|prop| prop_key(prop.as_prop()?))
+        .collect()
+}
+
+/** The key of a property, if it's an identifier or a string that is one */
+pub fn prop_key(prop: &Prop) -> Option<String> {
+    match prop {
+        Prop::Shorthand(ident) => Some(ident.sym.to_string()),
+        Prop::KeyValue(KeyValueProp { key, .. })
+        | Prop::Method(MethodProp { key, .. })
+        | Prop::Getter(GetterProp { key, .. })
+        | Prop::Setter(SetterProp { key, .. }) => Some(ident_from_prop_name(key)?.sym.to_string()),
+        _ => None,
+    }
+}
+
+/** The keys of the object returned from a data() function */
+pub fn data_keys(data: &Function) -> Vec<String> {
+    data.body
+        .iter()
+        .flat_map(|body| body.stmts.iter())
+        .find_map(|stmt| match stmt {
+            Stmt::Return(ReturnStmt { arg: Some(arg), .. }) => Some(object_keys(arg)),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+pub fn prop_set_from_object_lit(expr: &Expr) -> Option<HashSet<String>> {
     let mut set: Option<HashSet<String>> = None;
 
     // Handle arrays
-    if let Expr::Array(arr) = &**expr {
+    if let Expr::Array(arr) = expr {
         let items: Vec<String> = arr
             .elems
             .iter()
@@ -183,18 +620,18 @@ pub fn prop_set_from_object_lit(expr: &Box<Expr>) -> Option<HashSet<String>> {
                     return Some(s.value.to_string());
                 }
 
-                return None;
+                None
             })
             .collect();
 
         // Finally set if necessary
-        if items.len() > 0 {
+        if !items.is_empty() {
             set = Some(HashSet::from_iter(items));
         }
     }
 
     // Handle Objects
-    if let Expr::Object(obj) = &**expr {
+    if let Expr::Object(obj) = expr {
         let items: Vec<String> = obj
             .props
             .iter()
@@ -209,19 +646,347 @@ pub fn prop_set_from_object_lit(expr: &Box<Expr>) -> Option<HashSet<String>> {
                 }
 
                 let kv = prop.as_key_value().unwrap();
-                return match &kv.key {
+                match &kv.key {
                     PropName::Ident(id) => Some(id.sym.to_string()),
                     PropName::Str(s) => Some(s.value.to_string()),
                     _ => None,
-                };
+                }
             })
             .collect();
 
         // Finally set if necessary
-        if items.len() > 0 {
+        if !items.is_empty() {
             set = Some(HashSet::from_iter(items));
         }
     }
 
-    return set;
+    set
+}
+
+fn keyword_type(kind: TsKeywordTypeKind) -> TsType {
+    TsType::TsKeywordType(TsKeywordType {
+        span: Default::default(),
+        kind,
+    })
+}
+
+fn type_ref(name: &str, params: Vec<TsType>) -> TsType {
+    TsType::TsTypeRef(TsTypeRef {
+        span: Default::default(),
+        type_name: TsEntityName::Ident(Ident {
+            optional: false,
+            span: Default::default(),
+            sym: Atom::from(name),
+        }),
+        type_params: (!params.is_empty()).then(|| TsTypeParamInstantiation {
+            span: Default::default(),
+            params: params.into_iter().map(Box::new).collect(),
+        }),
+    })
+}
+
+/**
+ * The TypeScript type of a runtime prop `type`, e.g. `string` for `String`, `Foo[]` for
+ * `Array as PropType<Foo[]>` or `string | number` for `[String, Number]`
+ */
+pub fn ts_type_from_prop_type(expr: &Expr) -> Option<TsType> {
+    match expr {
+        Expr::Ident(ident) => Some(match &*ident.sym {
+            "String" => keyword_type(TsKeywordTypeKind::TsStringKeyword),
+            "Number" => keyword_type(TsKeywordTypeKind::TsNumberKeyword),
+            "Boolean" => keyword_type(TsKeywordTypeKind::TsBooleanKeyword),
+            "Symbol" => keyword_type(TsKeywordTypeKind::TsSymbolKeyword),
+            "BigInt" => keyword_type(TsKeywordTypeKind::TsBigIntKeyword),
+            "Array" => TsType::TsArrayType(TsArrayType {
+                span: Default::default(),
+                elem_type: Box::new(keyword_type(TsKeywordTypeKind::TsUnknownKeyword)),
+            }),
+            "Object" => type_ref(
+                "Record",
+                vec![
+                    keyword_type(TsKeywordTypeKind::TsStringKeyword),
+                    keyword_type(TsKeywordTypeKind::TsAnyKeyword),
+                ],
+            ),
+            // Classes and the other constructors are checked with instanceof
+            name => type_ref(name, vec![]),
+        }),
+        // `Foo as PropType<T>` is a `T`
+        Expr::TsAs(TsAsExpr { type_ann, .. }) => match &**type_ann {
+            TsType::TsTypeRef(TsTypeRef {
+                type_name: TsEntityName::Ident(name),
+                type_params: Some(type_params),
+                ..
+            }) if &*name.sym == "PropType" => type_params.params.first().map(|ty| (**ty).clone()),
+            _ => None,
+        },
+        Expr::Array(arr) => {
+            let types = arr
+                .elems
+                .iter()
+                .map(|elem| match elem {
+                    Some(ExprOrSpread { spread: None, expr }) => {
+                        ts_type_from_prop_type(expr).map(Box::new)
+                    }
+                    _ => None,
+                })
+                .collect::<Option<Vec<Box<TsType>>>>()?;
+
+            Some(TsType::TsUnionOrIntersectionType(
+                TsUnionOrIntersectionType::TsUnionType(TsUnionType {
+                    span: Default::default(),
+                    types,
+                }),
+            ))
+        }
+        Expr::Paren(ParenExpr { expr, .. }) => ts_type_from_prop_type(expr),
+        _ => None,
+    }
+}
+
+fn prop_decl(name: String, span: Span, value: &Expr) -> PropDecl {
+    let mut decl = PropDecl {
+        name,
+        span,
+        ty: None,
+        required: false,
+        default: None,
+        has_validator: false,
+    };
+
+    // A bare type, e.g. `id: String`
+    let obj = match value {
+        Expr::Object(obj) => obj,
+        _ => {
+            decl.ty = ts_type_from_prop_type(value).map(Box::new);
+            return decl;
+        }
+    };
+
+    let key = PropName::Ident(Ident {
+        optional: false,
+        span: Default::default(),
+        sym: Atom::from(decl.name.as_str()),
+    });
+    for prop in obj.props.iter().filter_map(|prop| prop.as_prop()) {
+        match &**prop {
+            Prop::KeyValue(kv) => match ident_from_prop_name(&kv.key).map(|id| id.sym) {
+                Some(sym) if &*sym == "type" => {
+                    decl.ty = ts_type_from_prop_type(&kv.value).map(Box::new)
+                }
+                Some(sym) if &*sym == "required" => {
+                    decl.required = matches!(&*kv.value, Expr::Lit(Lit::Bool(b)) if b.value)
+                }
+                Some(sym) if &*sym == "default" => {
+                    decl.default = Some(Prop::KeyValue(KeyValueProp {
+                        key: key.clone(),
+                        value: kv.value.clone(),
+                    }))
+                }
+                Some(sym) if &*sym == "validator" => decl.has_validator = true,
+                _ => {}
+            },
+            Prop::Method(method) => match ident_from_prop_name(&method.key).map(|id| id.sym) {
+                Some(sym) if &*sym == "default" => {
+                    decl.default = Some(Prop::Method(MethodProp {
+                        key: key.clone(),
+                        function: method.function.clone(),
+                    }))
+                }
+                Some(sym) if &*sym == "validator" => decl.has_validator = true,
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
+    decl
+}
+
+/**
+ * Read each prop of a runtime `props` declaration, returning `None` if the props can't be listed
+ * statically, e.g. because of a spread. Props declared as an array of names have no type.
+ */
+pub fn prop_decls_from_object_lit(expr: &Expr) -> Option<Vec<PropDecl>> {
+    match expr {
+        Expr::Array(arr) => arr
+            .elems
+            .iter()
+            .map(|elem| match elem.as_ref().map(|elem| &*elem.expr) {
+                Some(Expr::Lit(Lit::Str(name))) => Some(PropDecl {
+                    name: name.value.to_string(),
+                    span: name.span,
+                    ty: None,
+                    required: false,
+                    default: None,
+                    has_validator: false,
+                }),
+                _ => None,
+            })
+            .collect(),
+        Expr::Object(obj) => obj
+            .props
+            .iter()
+            .map(|prop| match prop.as_prop().map(|prop| &**prop) {
+                Some(Prop::KeyValue(kv)) => {
+                    let name = ident_from_prop_name(&kv.key)?.sym.to_string();
+                    Some(prop_decl(name, kv.span(), &kv.value))
+                }
+                _ => None,
+            })
+            .collect(),
+        _ => None,
+    }
+}
+
+/** Collects the names a pattern binds, like `a` and `b` in `{ a, b: [b] = [] }` */
+#[derive(Default)]
+struct PatNames(Vec<String>);
+
+impl Visit for PatNames {
+    fn visit_binding_ident(&mut self, ident: &BindingIdent) {
+        self.0.push(ident.id.sym.to_string());
+    }
+
+    // `{ a = 1 }`, the default is an expression
+    fn visit_assign_pat_prop(&mut self, prop: &AssignPatProp) {
+        self.0.push(prop.key.sym.to_string());
+    }
+
+    // Defaults and computed keys don't bind anything
+    fn visit_expr(&mut self, _: &Expr) {}
+
+    fn visit_prop_name(&mut self, _: &PropName) {}
+}
+
+/** The names bound by `pats` */
+pub fn pat_names<'a>(pats: impl IntoIterator<Item = &'a Pat>) -> Vec<String> {
+    let mut names = PatNames::default();
+    for pat in pats {
+        pat.visit_with(&mut names);
+    }
+    names.0
+}
+
+/**
+ * The names bound by `var`. When `this` is the component, declarators initialized with `this`
+ * are left out, since they're split into aliases rather than declared.
+ */
+fn declared_names(var: &VarDecl, this_is_component: bool) -> Vec<String> {
+    pat_names(
+        var.decls
+            .iter()
+            .filter(|decl| !(this_is_component && decl.init.as_deref().is_some_and(Expr::is_this)))
+            .map(|decl| &decl.name),
+    )
+}
+
+/** Collects the names declared with `var` in a function body, which are hoisted out of blocks */
+struct VarNames {
+    names: Vec<String>,
+    this_is_component: bool,
+}
+
+impl Visit for VarNames {
+    fn visit_var_decl(&mut self, var: &VarDecl) {
+        if var.kind == VarDeclKind::Var {
+            self.names
+                .extend(declared_names(var, self.this_is_component));
+        }
+    }
+
+    // Nested functions have their own scope, and expressions can't declare variables
+    fn visit_function(&mut self, _: &Function) {}
+
+    fn visit_class(&mut self, _: &Class) {}
+
+    fn visit_expr(&mut self, _: &Expr) {}
+}
+
+/** The names a function with `params` and `body` declares for its whole body */
+pub fn function_scope_names<'a>(
+    params: impl IntoIterator<Item = &'a Pat>,
+    body: Option<&BlockStmt>,
+    this_is_component: bool,
+) -> Vec<String> {
+    let mut names = pat_names(params);
+    if let Some(body) = body {
+        let mut vars = VarNames {
+            names: vec![],
+            this_is_component,
+        };
+        body.visit_with(&mut vars);
+        names.extend(vars.names);
+    }
+    names
+}
+
+/** The names the statements of a block declare for that block with let, const, function or class */
+pub fn block_scope_names(stmts: &[Stmt], this_is_component: bool) -> Vec<String> {
+    stmts
+        .iter()
+        .flat_map(|stmt| match stmt {
+            Stmt::Decl(Decl::Var(var)) if var.kind != VarDeclKind::Var => {
+                declared_names(var, this_is_component)
+            }
+            Stmt::Decl(Decl::Fn(decl)) => vec![decl.ident.sym.to_string()],
+            Stmt::Decl(Decl::Class(decl)) => vec![decl.ident.sym.to_string()],
+            _ => vec![],
+        })
+        .collect()
+}
+
+/** Finds a `return` outside of nested functions */
+#[derive(Default)]
+struct HasReturn(bool);
+
+impl Visit for HasReturn {
+    fn visit_return_stmt(&mut self, _: &ReturnStmt) {
+        self.0 = true;
+    }
+
+    fn visit_function(&mut self, _: &Function) {}
+
+    fn visit_arrow_expr(&mut self, _: &ArrowExpr) {}
+
+    fn visit_constructor(&mut self, _: &Constructor) {}
+
+    fn visit_getter_prop(&mut self, _: &GetterProp) {}
+
+    fn visit_setter_prop(&mut self, _: &SetterProp) {}
+}
+
+/** Whether any of `stmts` returns from the function they're in */
+pub fn has_return(stmts: &[Stmt]) -> bool {
+    let mut finder = HasReturn::default();
+    stmts.visit_with(&mut finder);
+    finder.0
+}
+
+/** The property of `attrs` Vue 3 passes the listener of `event` as, e.g. `onClick` for `click` */
+pub fn listener_prop(event: &str) -> String {
+    let mut chars = event.chars();
+    match chars.next() {
+        Some(first) => format!("on{}{}", first.to_uppercase(), chars.as_str()),
+        None => "on".to_string(),
+    }
+}
+
+/** The identifier an expression like `a.b.c` or `a` starts with */
+pub fn root_ident(expr: &Expr) -> Option<&Ident> {
+    match expr {
+        Expr::Ident(ident) => Some(ident),
+        Expr::Member(member) => root_ident(&member.obj),
+        _ => None,
+    }
+}
+
+/** The names declared by the head of a `for`, `for in` or `for of` loop */
+pub fn loop_head_names(head: Option<&VarDecl>) -> Vec<String> {
+    match head {
+        Some(var) if var.kind != VarDeclKind::Var => {
+            pat_names(var.decls.iter().map(|decl| &decl.name))
+        }
+        _ => vec![],
+    }
 }
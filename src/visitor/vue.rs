@@ -1,22 +1,197 @@
+use std::collections::HashMap;
+
+use swc_common::Span;
 use swc_ecma_ast::*;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Inject {
     pub name: String,
+    pub span: Span,
     pub from: Box<Expr>,
     pub default: Option<Box<Expr>>,
 }
 
+/** The built in properties and methods of a component instance */
+const INSTANCE_PROPERTIES: [&str; 26] = [
+    "$attrs",
+    "$children",
+    "$createElement",
+    "$data",
+    "$delete",
+    "$destroy",
+    "$el",
+    "$emit",
+    "$forceUpdate",
+    "$isServer",
+    "$listeners",
+    "$mount",
+    "$nextTick",
+    "$off",
+    "$on",
+    "$once",
+    "$options",
+    "$parent",
+    "$props",
+    "$root",
+    "$scopedSlots",
+    "$set",
+    "$slots",
+    "$ssrContext",
+    "$vnode",
+    "$watch",
+];
+
+/** The instance properties that are reached through the setup context */
+const CONTEXT_PROPERTIES: [&str; 5] = ["$attrs", "$emit", "$listeners", "$scopedSlots", "$slots"];
+
+/**
+ * The lifecycle hooks that become `on*` calls, by option name. `beforeDestroy` and `destroyed`
+ * were renamed to `beforeUnmount` and `unmounted` in Vue 3.
+ */
+pub const LIFECYCLE_HOOKS: [(&str, &str); 12] = [
+    ("beforeMount", "onBeforeMount"),
+    ("mounted", "onMounted"),
+    ("beforeUpdate", "onBeforeUpdate"),
+    ("updated", "onUpdated"),
+    ("activated", "onActivated"),
+    ("deactivated", "onDeactivated"),
+    ("beforeDestroy", "onBeforeUnmount"),
+    ("beforeUnmount", "onBeforeUnmount"),
+    ("destroyed", "onUnmounted"),
+    ("unmounted", "onUnmounted"),
+    ("errorCaptured", "onErrorCaptured"),
+    ("serverPrefetch", "onServerPrefetch"),
+];
+
+/** What a member of the component, reached through `this`, is */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Binding {
+    // A prop, reached through `props`
+    Prop,
+
+    // A property returned from data(), which becomes a ref
+    Data,
+
+    // A computed property, which becomes a computed ref
+    Computed,
+
+    // A method, which becomes a function
+    Method,
+
+    // An injection, which becomes a plain variable
+    Inject,
+
+    // The template refs, `this.$refs`
+    TemplateRef,
+
+    // A property of the instance that the setup context provides, like `$emit` or `$attrs`
+    Context,
+
+    // A built in property or method of the instance, like `$el` or `$nextTick`
+    InstanceProperty,
+
+    // Any other `$` property, usually added to the prototype by a plugin
+    Global,
+}
+
+/** Every member of a component, so each `this.x` can be rewritten according to what `x` is */
+#[derive(Debug, Default)]
+pub struct Bindings(HashMap<String, Binding>);
+impl Bindings {
+    pub fn insert(&mut self, name: impl Into<String>, binding: Binding) {
+        self.0.insert(name.into(), binding);
+    }
+
+    /** What `name` is, or `None` if the component doesn't declare it */
+    pub fn get(&self, name: &str) -> Option<Binding> {
+        if let Some(binding) = self.0.get(name) {
+            return Some(*binding);
+        }
+
+        match name {
+            "$refs" => Some(Binding::TemplateRef),
+            _ if CONTEXT_PROPERTIES.contains(&name) => Some(Binding::Context),
+            _ if INSTANCE_PROPERTIES.contains(&name) => Some(Binding::InstanceProperty),
+            _ if name.starts_with('$') => Some(Binding::Global),
+            _ => None,
+        }
+    }
+}
+
+/** What a local name, declared from `this` inside an option function, stands for */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Alias {
+    // The instance itself, e.g. `const self = this`
+    This,
+
+    // A member of the instance, e.g. `items` from `const { items } = this`
+    Member(Ident),
+}
+
+/** A prop read from the runtime `props` declaration, for writing a type-based declaration */
 #[derive(Clone, Debug)]
-pub struct WatchDecl {
+pub struct PropDecl {
+    pub name: String,
+    pub span: Span,
+
+    // The type of the prop, if it could be derived from its `type`
+    pub ty: Option<Box<TsType>>,
+
+    pub required: bool,
+
+    // The default, as a property of the object passed to `withDefaults`
+    pub default: Option<Prop>,
+
+    // Whether the prop has a validator, which a type can't express
+    pub has_validator: bool,
+}
+
+/** A computed property, from a getter or an object with `get` and `set` */
+#[derive(Clone, Debug)]
+pub struct ComputedDecl {
     pub ident: Ident,
-    pub function: Function,
+
+    // The span of the whole entry, from the key to the end of the value
+    pub span: Span,
+
+    pub get: Function,
+    pub set: Option<Function>,
+}
+
+/** What is called when a watched source changes */
+#[derive(Clone, Debug)]
+pub enum WatchCallback {
+    // A handler function, written as a method, a function or an arrow function
+    Function(Function),
+
+    // The name of a method, e.g. `count: 'onCountChanged'`
+    Method(Ident),
+}
+
+/** The handler of a watcher, and the options it's watched with */
+#[derive(Clone, Debug)]
+pub struct WatchHandler {
+    pub callback: WatchCallback,
     pub deep: Option<Box<Expr>>,
     pub immediate: Option<Box<Expr>>,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
+pub struct WatchDecl {
+    // What's watched, a ref or a getter
+    pub source: Box<Expr>,
+
+    // The span of the whole entry, from the key to the end of the handler
+    pub span: Span,
+
+    pub handler: WatchHandler,
+}
+
+#[derive(Debug, Default)]
 pub struct OptionsComponent {
+    // The component name
+    pub name: Option<Box<Expr>>,
+
     // The components object
     pub components: Option<Box<Expr>>,
 
@@ -26,54 +201,105 @@ pub struct OptionsComponent {
     // The props
     pub props: Option<Box<Expr>>,
 
+    // The declared emits
+    pub emits: Option<Box<Expr>>,
+
+    // Whether attributes fall through to the root element
+    pub inherit_attrs: Option<Box<Expr>>,
+
     // The data() method
     pub data: Option<Function>,
 
-    // The computed methods
-    pub computed: Option<Vec<FnDecl>>,
+    // The computed properties
+    pub computed: Option<Vec<ComputedDecl>>,
 
     // The watch methods
     pub watch: Option<Vec<WatchDecl>>,
 
+    // The beforeCreate() method
+    pub before_create: Option<Function>,
+
     // The created() method
     pub created: Option<Function>,
 
-    // The mounted() method
-    pub mounted: Option<Function>,
+    // The other lifecycle hooks, like mounted(), in the order they're defined
+    pub hooks: Vec<(&'static str, Function)>,
 
     // The method object
     pub methods: Option<Vec<FnDecl>>,
+
+    // The options that aren't converted, like `mixins`, copied as is
+    pub passed_through: Vec<PropOrSpread>,
 }
-impl Default for OptionsComponent {
-    fn default() -> OptionsComponent {
-        Self {
-            components: None,
-            inject: None,
-            props: None,
-            data: None,
-            computed: None,
-            watch: None,
-            created: None,
-            mounted: None,
-            methods: None,
-        }
+impl OptionsComponent {
+    /** The names of the component options that were found and converted, in a fixed order */
+    pub fn handled_options(&self) -> Vec<&'static str> {
+        [
+            ("name", self.name.is_some()),
+            ("components", self.components.is_some()),
+            ("props", self.props.is_some()),
+            ("emits", self.emits.is_some()),
+            ("inheritAttrs", self.inherit_attrs.is_some()),
+            ("inject", self.inject.is_some()),
+            ("data", self.data.is_some()),
+            ("computed", self.computed.is_some()),
+            ("watch", self.watch.is_some()),
+            ("beforeCreate", self.before_create.is_some()),
+            ("created", self.created.is_some()),
+        ]
+        .into_iter()
+        .chain(
+            LIFECYCLE_HOOKS
+                .iter()
+                .map(|(hook, _)| (*hook, self.hooks.iter().any(|(name, _)| name == hook))),
+        )
+        .chain([("methods", self.methods.is_some())])
+        .filter_map(|(name, present)| present.then_some(name))
+        .collect()
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct CompositionComponent {
+    // The component name
+    pub name: Option<Box<Expr>>,
+
     // The components object
     pub components: Option<Box<Expr>>,
 
     // The props
     pub props: Option<Box<Expr>>,
 
+    // The props as a type-based declaration, used in place of `props` if set
+    pub typed_props: Option<Vec<PropDecl>>,
+
+    // The declared emits
+    pub emits: Option<Box<Expr>>,
+
+    // Whether attributes fall through to the root element
+    pub inherit_attrs: Option<Box<Expr>>,
+
+    // The options that aren't converted, written next to setup
+    pub passed_through: Vec<PropOrSpread>,
+
+    // Whether `this.$emit` is called anywhere
+    pub calls_emit: bool,
+
+    // The names of the events passed to `this.$emit`, in the order they're first emitted
+    pub emitted_events: Vec<String>,
+
     // The inject statments
     pub inject_stmts: Option<Vec<Stmt>>,
 
     // The ref statements, derived from the data method
     pub ref_stmts: Option<Vec<Stmt>>,
 
+    // The template ref statements, `const foo = ref(null)` for each `this.$refs.foo`
+    pub template_ref_stmts: Option<Vec<Stmt>>,
+
+    // The statements gathered from the beforeCreate method
+    pub before_create_stmts: Option<Vec<Stmt>>,
+
     // The statements gathered from the created method
     pub created_stmts: Option<Vec<Stmt>>,
 
@@ -83,24 +309,39 @@ pub struct CompositionComponent {
     // The statements gathered from the watch block
     pub watch: Option<Vec<Stmt>>,
 
-    // The statements to wrapped with onMounted, gathered from the mounted method
-    pub mounted: Option<Vec<Stmt>>,
+    // The `on*` calls of the lifecycle hooks, like `onMounted(() => {})`, by option name
+    pub hooks: Vec<(&'static str, Stmt)>,
 
     // The function declarations
     pub method_decls: Option<Vec<Stmt>>,
+
+    // The spans of the original option keys, so the comments on them are kept
+    pub key_spans: HashMap<String, Span>,
+
+    // The Vue APIs used by the generated code, in the order they're first used
+    pub imports: Vec<&'static str>,
+
+    // Whether the component instance is needed, to reach globals
+    pub uses_instance: bool,
+
+    // The properties of the setup context the code uses, in the order they're first used
+    pub context_properties: Vec<&'static str>,
+
+    // Whether the setup context is destructured in the signature, rather than bound to `ctx`
+    pub destructure_context: bool,
 }
-impl Default for CompositionComponent {
-    fn default() -> CompositionComponent {
-        Self {
-            components: None,
-            props: None,
-            inject_stmts: None,
-            ref_stmts: None,
-            created_stmts: None,
-            computed: None,
-            watch: None,
-            mounted: None,
-            method_decls: None,
+impl CompositionComponent {
+    /** Record that the generated code uses a Vue API, so it gets imported */
+    pub fn use_api(&mut self, name: &'static str) {
+        if !self.imports.contains(&name) {
+            self.imports.push(name);
+        }
+    }
+
+    /** Record that the generated code uses a property of the setup context, like `emit` */
+    pub fn use_context(&mut self, property: &'static str) {
+        if !self.context_properties.contains(&property) {
+            self.context_properties.push(property);
         }
     }
 }
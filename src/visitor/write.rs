@@ -1,9 +1,107 @@
 use string_cache::Atom;
+use swc_common::comments::{Comment, CommentKind, Comments, SingleThreadedComments};
+use swc_common::{BytePos, Span, Spanned};
 use swc_ecma_ast::*;
 
-use super::vue::CompositionComponent;
+use super::vue::{CompositionComponent, PropDecl};
+use crate::diagnostics::Diagnostic;
+use crate::options::Target;
 
-pub fn write_setup(mut stmts: Vec<Stmt>) -> MethodProp {
+/** The name the component instance proxy is bound to inside `setup` */
+pub const INSTANCE_IDENT: &str = "instance";
+
+/** `const instance = getCurrentInstance().proxy;` */
+fn write_instance_decl() -> Stmt {
+    Stmt::Decl(Decl::Var(VarDecl {
+        span: Default::default(),
+        declare: false,
+        kind: VarDeclKind::Const,
+        decls: vec![VarDeclarator {
+            span: Default::default(),
+            definite: false,
+            name: Pat::Ident(BindingIdent {
+                type_ann: None,
+                id: Ident {
+                    optional: false,
+                    span: Default::default(),
+                    sym: Atom::from(INSTANCE_IDENT),
+                },
+            }),
+            init: Some(Box::new(Expr::Member(MemberExpr {
+                span: Default::default(),
+                obj: Box::new(Expr::Call(CallExpr {
+                    span: Default::default(),
+                    type_args: None,
+                    callee: Callee::Expr(Box::new(Expr::Ident(Ident {
+                        optional: false,
+                        span: Default::default(),
+                        sym: Atom::from("getCurrentInstance"),
+                    }))),
+                    args: vec![],
+                })),
+                prop: MemberProp::Ident(Ident {
+                    optional: false,
+                    span: Default::default(),
+                    sym: Atom::from("proxy"),
+                }),
+            }))),
+        }],
+    }))
+}
+
+/** The composable that reads a property of the setup context in script setup, e.g. `useAttrs` */
+pub fn context_composable(property: &str) -> Option<&'static str> {
+    match property {
+        "attrs" => Some("useAttrs"),
+        "slots" => Some("useSlots"),
+        "listeners" => Some("useListeners"),
+        _ => None,
+    }
+}
+
+/**
+ * The second parameter of setup, `ctx`, or a pattern of the context properties that are used if
+ * the context is destructured
+ */
+fn write_context_param(obj: &CompositionComponent) -> Option<Pat> {
+    if !obj.destructure_context {
+        return Some(Pat::Ident(BindingIdent {
+            type_ann: None,
+            id: Ident {
+                span: Default::default(),
+                sym: Atom::from("ctx"),
+                optional: false,
+            },
+        }));
+    }
+
+    if obj.context_properties.is_empty() {
+        return None;
+    }
+
+    Some(Pat::Object(ObjectPat {
+        span: Default::default(),
+        optional: false,
+        type_ann: None,
+        props: obj
+            .context_properties
+            .iter()
+            .map(|property| {
+                ObjectPatProp::Assign(AssignPatProp {
+                    span: Default::default(),
+                    key: Ident {
+                        optional: false,
+                        span: Default::default(),
+                        sym: Atom::from(*property),
+                    },
+                    value: None,
+                })
+            })
+            .collect(),
+    }))
+}
+
+pub fn write_setup(mut stmts: Vec<Stmt>, context: Option<Pat>) -> MethodProp {
     // Declarations that will need to be included in the return statement
     let mut declarations: Vec<Ident> = vec![];
     for stmt in stmts.iter() {
@@ -35,7 +133,7 @@ pub fn write_setup(mut stmts: Vec<Stmt>) -> MethodProp {
         }))),
     }));
 
-    return MethodProp {
+    MethodProp {
         key: PropName::Ident(Ident {
             optional: false,
             span: Default::default(),
@@ -47,69 +145,58 @@ pub fn write_setup(mut stmts: Vec<Stmt>) -> MethodProp {
             return_type: None,
             type_params: None,
             span: Default::default(),
-            params: vec![
-                Param {
-                    decorators: vec![],
+            params: std::iter::once(Pat::Ident(BindingIdent {
+                type_ann: None,
+                id: Ident {
                     span: Default::default(),
-                    pat: Pat::Ident(BindingIdent {
-                        type_ann: None,
-                        id: Ident {
-                            span: Default::default(),
-                            sym: Atom::from("props"),
-                            optional: false,
-                        },
-                    }),
+                    sym: Atom::from("props"),
+                    optional: false,
                 },
-                Param {
-                    decorators: vec![],
-                    span: Default::default(),
-                    pat: Pat::Ident(BindingIdent {
-                        type_ann: None,
-                        id: Ident {
-                            span: Default::default(),
-                            sym: Atom::from("ctx"),
-                            optional: false,
-                        },
-                    }),
-                },
-            ],
+            }))
+            .chain(context)
+            .map(|pat| Param {
+                decorators: vec![],
+                span: Default::default(),
+                pat,
+            })
+            .collect(),
             body: Some(BlockStmt {
                 span: Default::default(),
                 stmts,
             }),
             decorators: vec![],
         },
-    };
+    }
 }
 
-pub fn write_composition_component(obj: &CompositionComponent) -> ExportDefaultExpr {
-    let mut export_props: Vec<PropOrSpread> = vec![];
-
-    // TODO: Component and props probably don't need to be stored on composition component
-    // Inject Components
-    if let Some(components) = &obj.components {
-        export_props.push(PropOrSpread::Prop(Box::new(Prop::KeyValue(KeyValueProp {
-            key: PropName::Ident(Ident {
-                optional: false,
-                span: Default::default(),
-                sym: Atom::from("components"),
-            }),
-            value: components.clone(),
-        }))));
-    }
+/** `key: value`, keeping the span of the original option key so its comments are kept */
+fn write_option(obj: &CompositionComponent, key: &str, value: &Expr) -> PropOrSpread {
+    PropOrSpread::Prop(Box::new(Prop::KeyValue(KeyValueProp {
+        key: PropName::Ident(Ident {
+            optional: false,
+            span: obj.key_spans.get(key).copied().unwrap_or_default(),
+            sym: Atom::from(key),
+        }),
+        value: Box::new(value.clone()),
+    })))
+}
 
-    // Inject Props
-    if let Some(props) = &obj.props {
-        export_props.push(PropOrSpread::Prop(Box::new(Prop::KeyValue(KeyValueProp {
-            key: PropName::Ident(Ident {
-                optional: false,
-                span: Default::default(),
-                sym: Atom::from("props"),
-            }),
-            value: props.clone(),
-        }))));
-    }
+/** The options that are passed through as is, in the order they're written */
+fn passed_through_options(obj: &CompositionComponent) -> Vec<(&'static str, &Expr)> {
+    [
+        ("name", &obj.name),
+        ("components", &obj.components),
+        ("props", &obj.props),
+        ("emits", &obj.emits),
+        ("inheritAttrs", &obj.inherit_attrs),
+    ]
+    .into_iter()
+    .filter_map(|(key, value)| Some((key, &**value.as_ref()?)))
+    .collect()
+}
 
+/** The body of setup, in the order that each section should be declared */
+fn write_setup_stmts(obj: &CompositionComponent) -> Vec<Stmt> {
     let mut setup_stmts: Vec<Stmt> = vec![];
 
     // Inject inject
@@ -122,6 +209,11 @@ pub fn write_composition_component(obj: &CompositionComponent) -> ExportDefaultE
         setup_stmts.extend(refs.clone());
     }
 
+    // Inject template refs
+    if let Some(refs) = &obj.template_ref_stmts {
+        setup_stmts.extend(refs.clone());
+    }
+
     // Inject Computed
     if let Some(fn_decls) = &obj.computed {
         setup_stmts.extend(fn_decls.clone());
@@ -132,6 +224,11 @@ pub fn write_composition_component(obj: &CompositionComponent) -> ExportDefaultE
         setup_stmts.extend(watch_decls.clone());
     }
 
+    // Inject beforeCreate, which runs right before created
+    if let Some(before_create) = &obj.before_create_stmts {
+        setup_stmts.extend(before_create.clone());
+    }
+
     // Inject created
     if let Some(created) = &obj.created_stmts {
         setup_stmts.extend(created.clone());
@@ -142,15 +239,27 @@ pub fn write_composition_component(obj: &CompositionComponent) -> ExportDefaultE
         setup_stmts.extend(methods.clone());
     }
 
-    // Inject mounted
-    if let Some(mounted) = &obj.mounted {
-        setup_stmts.extend(mounted.clone());
-    }
+    // Inject lifecycle hooks
+    setup_stmts.extend(obj.hooks.iter().map(|(_, stmt)| stmt.clone()));
 
-    // Finally, write setup
-    export_props.push(PropOrSpread::Prop(Box::new(Prop::Method(write_setup(
-        setup_stmts,
-    )))));
+    setup_stmts
+}
+
+pub fn write_composition_component(obj: &CompositionComponent) -> ExportDefaultExpr {
+    let mut export_props: Vec<PropOrSpread> = passed_through_options(obj)
+        .into_iter()
+        .map(|(key, value)| write_option(obj, key, value))
+        .chain(obj.passed_through.iter().cloned())
+        .collect();
+
+    // Finally, write setup. The instance is declared afterwards, so it isn't returned
+    let mut setup = write_setup(write_setup_stmts(obj), write_context_param(obj));
+    if obj.uses_instance {
+        if let Some(body) = &mut setup.function.body {
+            body.stmts.insert(0, write_instance_decl());
+        }
+    }
+    export_props.push(PropOrSpread::Prop(Box::new(Prop::Method(setup))));
 
     // Return entire defineComponent export
     ExportDefaultExpr {
@@ -173,3 +282,392 @@ pub fn write_composition_component(obj: &CompositionComponent) -> ExportDefaultE
         })),
     }
 }
+
+/** A call to a compiler macro like `defineProps`, with an optional type argument */
+fn macro_call(callee: &str, args: Vec<Expr>, type_arg: Option<TsType>) -> Expr {
+    Expr::Call(CallExpr {
+        span: Default::default(),
+        type_args: type_arg.map(|ty| TsTypeParamInstantiation {
+            span: Default::default(),
+            params: vec![Box::new(ty)],
+        }),
+        callee: Callee::Expr(Box::new(Expr::Ident(Ident {
+            optional: false,
+            span: Default::default(),
+            sym: Atom::from(callee),
+        }))),
+        args: args
+            .into_iter()
+            .map(|expr| ExprOrSpread {
+                spread: None,
+                expr: Box::new(expr),
+            })
+            .collect(),
+    })
+}
+
+/** A statement calling a compiler macro, optionally bound to `name` */
+fn write_macro_stmt(span: Span, call: Expr, name: Option<&str>) -> Stmt {
+    match name {
+        Some(name) => Stmt::Decl(Decl::Var(VarDecl {
+            span,
+            declare: false,
+            kind: VarDeclKind::Const,
+            decls: vec![VarDeclarator {
+                span: Default::default(),
+                definite: false,
+                name: Pat::Ident(BindingIdent {
+                    type_ann: None,
+                    id: Ident {
+                        optional: false,
+                        span: Default::default(),
+                        sym: Atom::from(name),
+                    },
+                }),
+                init: Some(Box::new(call)),
+            }],
+        })),
+        None => Stmt::Expr(ExprStmt {
+            span,
+            expr: Box::new(call),
+        }),
+    }
+}
+
+/**
+ * `defineProps<{ ... }>()`, wrapped in `withDefaults(..., { ... })` if any prop has a default.
+ * Every prop must have a type.
+ */
+fn write_typed_props(decls: &[PropDecl]) -> Expr {
+    let members = decls
+        .iter()
+        .map(|decl| {
+            TsTypeElement::TsPropertySignature(TsPropertySignature {
+                span: decl.span,
+                readonly: false,
+                key: Box::new(Expr::Ident(Ident {
+                    optional: false,
+                    span: Default::default(),
+                    sym: Atom::from(decl.name.as_str()),
+                })),
+                computed: false,
+                optional: !decl.required,
+                init: None,
+                params: vec![],
+                type_ann: decl.ty.clone().map(|type_ann| TsTypeAnn {
+                    span: Default::default(),
+                    type_ann,
+                }),
+                type_params: None,
+            })
+        })
+        .collect();
+    let define_props = macro_call(
+        "defineProps",
+        vec![],
+        Some(TsType::TsTypeLit(TsTypeLit {
+            span: Default::default(),
+            members,
+        })),
+    );
+
+    let defaults: Vec<PropOrSpread> = decls
+        .iter()
+        .filter_map(|decl| decl.default.clone())
+        .map(|prop| PropOrSpread::Prop(Box::new(prop)))
+        .collect();
+    if defaults.is_empty() {
+        return define_props;
+    }
+
+    let defaults = Expr::Object(ObjectLit {
+        span: Default::default(),
+        props: defaults,
+    });
+    macro_call("withDefaults", vec![define_props, defaults], None)
+}
+
+/** The options of a `<script setup>` component that have no macro of their own, if there are any */
+fn script_setup_options(obj: &CompositionComponent) -> Option<Expr> {
+    let options: Vec<PropOrSpread> = passed_through_options(obj)
+        .into_iter()
+        .filter(|(key, _)| matches!(*key, "name" | "inheritAttrs"))
+        .map(|(key, value)| write_option(obj, key, value))
+        .chain(obj.passed_through.iter().cloned())
+        .collect();
+    (!options.is_empty()).then(|| {
+        Expr::Object(ObjectLit {
+            span: Default::default(),
+            props: options,
+        })
+    })
+}
+
+/**
+ * The statements of a `<script setup>` block: `defineProps`, `defineEmits` and `defineOptions`
+ * followed by the body of setup. Nothing is returned, since every top level binding is exposed.
+ * Before Vue 3.3 there's no `defineOptions`, see `write_plain_script`.
+ */
+pub fn write_script_setup(obj: &CompositionComponent, target: Target) -> Vec<Stmt> {
+    let key_span = |key: &str| obj.key_spans.get(key).copied().unwrap_or_default();
+    let mut stmts: Vec<Stmt> = vec![];
+
+    if let Some(props) = &obj.props {
+        let call = match &obj.typed_props {
+            Some(decls) => write_typed_props(decls),
+            None => macro_call("defineProps", vec![(**props).clone()], None),
+        };
+        stmts.push(write_macro_stmt(key_span("props"), call, Some("props")));
+    }
+
+    // Declared emits take precedence over the events found in the code
+    let emits = obj
+        .emits
+        .as_ref()
+        .map(|emits| (**emits).clone())
+        .or_else(|| {
+            obj.calls_emit.then(|| {
+                Expr::Array(ArrayLit {
+                    span: Default::default(),
+                    elems: obj
+                        .emitted_events
+                        .iter()
+                        .map(|event| {
+                            Some(ExprOrSpread {
+                                spread: None,
+                                expr: Box::new(Expr::Lit(Lit::Str(Str {
+                                    span: Default::default(),
+                                    value: event.as_str().into(),
+                                    raw: Some(format!("'{}'", event).into()),
+                                }))),
+                            })
+                        })
+                        .collect(),
+                })
+            })
+        });
+    if let Some(emits) = emits {
+        let name = obj.calls_emit.then_some("emit");
+        stmts.push(write_macro_stmt(
+            key_span("emits"),
+            macro_call("defineEmits", vec![emits], None),
+            name,
+        ));
+    }
+
+    // Options that have no macro of their own
+    let options = script_setup_options(obj).filter(|_| target.has_define_options());
+    if let Some(options) = options {
+        stmts.push(write_macro_stmt(
+            Default::default(),
+            macro_call("defineOptions", vec![options], None),
+            None,
+        ));
+    }
+
+    if obj.uses_instance {
+        stmts.push(write_instance_decl());
+    }
+
+    // `const attrs = useAttrs();`, since there's no setup context
+    for property in obj.context_properties.iter() {
+        if let Some(composable) = context_composable(property) {
+            stmts.push(write_macro_stmt(
+                Default::default(),
+                macro_call(composable, vec![], None),
+                Some(property),
+            ));
+        }
+    }
+    stmts.extend(write_setup_stmts(obj));
+    stmts
+}
+
+/**
+ * The items of the plain `<script>` block written next to `<script setup>`: the named `exports`,
+ * which `<script setup>` can't have, and the options without a macro if there's no
+ * `defineOptions`. Empty if there's no need for the block.
+ */
+pub fn write_plain_script(
+    obj: &CompositionComponent,
+    target: Target,
+    mut exports: Vec<ModuleItem>,
+) -> Vec<ModuleItem> {
+    let options = script_setup_options(obj).filter(|_| !target.has_define_options());
+    if let Some(options) = options {
+        exports.push(ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultExpr(
+            ExportDefaultExpr {
+                span: Default::default(),
+                expr: Box::new(options),
+            },
+        )));
+    }
+    exports
+}
+
+fn named_specifier(name: &str) -> ImportSpecifier {
+    ImportSpecifier::Named(ImportNamedSpecifier {
+        span: Default::default(),
+        local: Ident {
+            optional: false,
+            span: Default::default(),
+            sym: Atom::from(name),
+        },
+        imported: None,
+        is_type_only: false,
+    })
+}
+
+/** The local names bound by every import of the module, with the module each comes from */
+fn imported_bindings(module: &Module) -> Vec<(String, String, Span)> {
+    module
+        .body
+        .iter()
+        .filter_map(|item| item.as_module_decl()?.as_import())
+        .flat_map(|import| {
+            import.specifiers.iter().map(|specifier| {
+                let local = match specifier {
+                    ImportSpecifier::Named(named) => &named.local,
+                    ImportSpecifier::Default(default) => &default.local,
+                    ImportSpecifier::Namespace(namespace) => &namespace.local,
+                };
+                (
+                    local.sym.to_string(),
+                    import.src.value.to_string(),
+                    local.span,
+                )
+            })
+        })
+        .collect()
+}
+
+/** Whether a comment is a doc comment, `/** ... */`, which describes the code right after it */
+fn is_doc_comment(comment: &Comment) -> bool {
+    comment.kind == CommentKind::Block && comment.text.starts_with('*')
+}
+
+/**
+ * Move the header of the file, like `/* eslint-disable */` or a license, out of the comments
+ * leading its first item at `lo`, so an import can be put above that item but below the header.
+ * Only the doc comments right before the item stay with it. The returned span leads the header,
+ * and is dummy if there's none.
+ */
+fn split_header_comments(comments: &SingleThreadedComments, lo: BytePos) -> Span {
+    let mut leading = match comments.take_leading(lo) {
+        Some(leading) => leading,
+        None => return Span::default(),
+    };
+    let attached = leading
+        .iter()
+        .rposition(|comment| !is_doc_comment(comment))
+        .map_or(0, |index| index + 1);
+    let header: Vec<Comment> = leading.drain(..attached).collect();
+    if !leading.is_empty() {
+        comments.add_leading_comments(lo, leading);
+    }
+    if header.is_empty() {
+        return Span::default();
+    }
+
+    // Nothing else is keyed right before the first token of the file
+    let pos = lo - BytePos(1);
+    comments.add_leading_comments(pos, header);
+    Span::new(pos, pos, Default::default())
+}
+
+/**
+ * Import `names` from `source`, merging them into an existing named import of that module if there
+ * is one. Names that are already bound by an import are skipped, and if that import is from
+ * another module, like `'vue'` when the target is Vue 2.6, a diagnostic is returned for each.
+ */
+pub fn write_imports(
+    module: &mut Module,
+    source: &str,
+    names: &[&str],
+    comments: Option<&SingleThreadedComments>,
+) -> Vec<Diagnostic> {
+    let bound = imported_bindings(module);
+    let mut diagnostics = vec![];
+    let names: Vec<&str> = names
+        .iter()
+        .copied()
+        .filter(|name| match bound.iter().find(|(local, _, _)| local == name) {
+            Some((_, from, span)) => {
+                if from != source {
+                    diagnostics.push(
+                        Diagnostic::warning(
+                            "import-conflict",
+                            format!(
+                                "`{}` is already imported from '{}', so it was not imported from '{}'",
+                                name, from, source
+                            ),
+                        )
+                        .with_span(*span),
+                    );
+                }
+                false
+            }
+            None => true,
+        })
+        .collect();
+
+    let existing = module.body.iter_mut().find_map(|item| match item {
+        ModuleItem::ModuleDecl(ModuleDecl::Import(import))
+            if &*import.src.value == source
+                && !import.type_only
+                && !import.specifiers.iter().any(|s| s.is_namespace()) =>
+        {
+            Some(import)
+        }
+        _ => None,
+    });
+
+    let specifiers: Vec<ImportSpecifier> = names.iter().map(|name| named_specifier(name)).collect();
+    if specifiers.is_empty() {
+        return diagnostics;
+    }
+
+    if let Some(import) = existing {
+        import.specifiers.extend(specifiers);
+        return diagnostics;
+    }
+
+    // Otherwise add a new import after the existing ones, quoted like them
+    let quote = module
+        .body
+        .iter()
+        .find_map(|item| match item {
+            ModuleItem::ModuleDecl(ModuleDecl::Import(import)) => import.src.raw.as_ref(),
+            _ => None,
+        })
+        .and_then(|raw| raw.chars().next())
+        .unwrap_or('\'');
+    let index = module
+        .body
+        .iter()
+        .rposition(|item| matches!(item, ModuleItem::ModuleDecl(ModuleDecl::Import(_))))
+        .map_or(0, |index| index + 1);
+    // The module emits the leading comments of its first item, keep those with the item instead
+    let mut span = Span::default();
+    if index == 0 {
+        module.span = Default::default();
+        if let (Some(comments), Some(first)) = (comments, module.body.first()) {
+            span = split_header_comments(comments, first.span().lo);
+        }
+    }
+    module.body.insert(
+        index,
+        ModuleItem::ModuleDecl(ModuleDecl::Import(ImportDecl {
+            span,
+            specifiers,
+            src: Str {
+                span: Default::default(),
+                value: Atom::from(source),
+                raw: Some(format!("{}{}{}", quote, source, quote).into()),
+            },
+            type_only: false,
+            asserts: None,
+        })),
+    );
+    diagnostics
+}
@@ -0,0 +1,67 @@
+use otc::visitor::Visitor;
+use otc::{convert, Options};
+use swc_core::testing_transform::test;
+use swc_ecma_visit::as_folder;
+
+test!(
+    Default::default(),
+    |_| as_folder(Visitor::default()),
+    test_bindings,
+    // Input codes
+    r#"export default {
+        inject: ['thing'],
+        props: { propFn: Function, a: Number },
+        data() {
+            return { x: 1 };
+        },
+        computed: {
+            double() {
+                return this.x * 2;
+            },
+        },
+        methods: {
+            cb() {},
+            run() {
+                items.forEach(this.cb);
+                this.propFn(this.a);
+                console.log(this.thing, this.thing.y, this.double, this.$el);
+            },
+        },
+    };"#,
+    // Output codes after transformed with plugin
+    r#"import { defineComponent, getCurrentInstance, inject, ref, computed } from '@vue/composition-api';
+    export default defineComponent({
+        props: { propFn: Function, a: Number },
+        setup (props, ctx) {
+            const instance = getCurrentInstance().proxy;
+            const thing = inject('thing');
+            const x = ref(1);
+            const double = computed(()=>x.value * 2);
+
+            function cb() {}
+            function run() {
+                items.forEach(cb);
+                props.propFn(props.a);
+                console.log(thing, thing.y, double.value, instance.$el);
+            }
+
+            return {
+                thing,
+                x,
+                double,
+                cb,
+                run,
+            }
+        },
+    });"#
+);
+
+#[test]
+fn test_unknown_member() {
+    let source = "export default { methods: { run() { return this.fromMixin; } } };";
+
+    let conversion = convert(source, &Options::default()).unwrap();
+    assert!(conversion.output.contains("return fromMixin.value;"));
+    assert_eq!(conversion.diagnostics.len(), 1);
+    assert_eq!(conversion.diagnostics[0].code, "unknown-member");
+}
@@ -0,0 +1,163 @@
+use otc::{convert, Options};
+
+#[test]
+fn test_comments_are_preserved() {
+    let source = r#"/* eslint-disable no-console */
+/**
+ * The component
+ */
+export default {
+    props: {
+        // The id
+        id: String,
+    },
+    data() {
+        return {
+            // Is loading
+            loading: false,
+        };
+    },
+    computed: {
+        /** Double the count */
+        double() {
+            // inner comment
+            return this.count * 2;
+        },
+    },
+    // Runs on create
+    created() {
+        this.loading = true;
+    },
+    methods: {
+        /**
+         * Does a thing
+         */
+        method1(arg) {
+            console.log(arg); // log it
+        },
+    },
+};
+"#;
+
+    let output = convert(source, &Options::default()).unwrap().output;
+    assert_eq!(
+        output,
+        r#"/* eslint-disable no-console */
+import { defineComponent, ref, computed } from '@vue/composition-api';
+/**
+ * The component
+ */
+export default defineComponent({
+    props: {
+        // The id
+        id: String
+    },
+    setup (props, ctx) {
+        // Is loading
+        const loading = ref(false);
+        /** Double the count */
+        const double = computed(()=>{
+            // inner comment
+            return count.value * 2;
+        });
+        // Runs on create
+        loading.value = true;
+        /**
+         * Does a thing
+         */
+        function method1(arg) {
+            console.log(arg); // log it
+        }
+        return {
+            loading,
+            double,
+            method1
+        };
+    }
+});
+"#
+    );
+}
+
+#[test]
+fn test_comments_reindented() {
+    let source = r#"export default {
+  data() {
+    return {
+      count: 0, // starts at zero
+      name: '' // no comma
+    };
+  },
+  computed: {
+    /**
+     * Twice the count,
+     *   shown in the header
+     */
+    double() {
+      return this.count * 2;
+    },
+  },
+  methods: {
+        /**
+         * Deeply indented
+         */
+        run() {
+          /*
+           * Nested in the method
+           */
+          return this.double;
+        },
+  },
+};
+"#;
+
+    let output = convert(source, &Options::default()).unwrap().output;
+    assert_eq!(
+        output,
+        r#"import { defineComponent, ref, computed } from '@vue/composition-api';
+export default defineComponent({
+    setup (props, ctx) {
+        const count = ref(0); // starts at zero
+        const name = ref(''); // no comma
+        /**
+         * Twice the count,
+         *   shown in the header
+         */
+        const double = computed(()=>count.value * 2);
+        /**
+         * Deeply indented
+         */
+        function run() {
+            /*
+             * Nested in the method
+             */
+            return double.value;
+        }
+        return {
+            count,
+            name,
+            double,
+            run
+        };
+    }
+});
+"#
+    );
+}
+
+#[test]
+fn test_header_comments_stay_above_imports() {
+    let source = r#"// @ts-check
+/*! Licensed under MIT */
+export default {
+    data() {
+        return { count: 0 };
+    },
+};
+"#;
+
+    let output = convert(source, &Options::default()).unwrap().output;
+    assert!(output.starts_with(
+        "// @ts-check\n/*! Licensed under MIT */\nimport { defineComponent, ref } from '@vue/composition-api';\nexport default defineComponent({"
+    ));
+}
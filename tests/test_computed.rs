@@ -0,0 +1,91 @@
+use otc::visitor::Visitor;
+use otc::{convert, Options};
+use swc_core::testing_transform::test;
+use swc_ecma_visit::as_folder;
+
+test!(
+    Default::default(),
+    |_| as_folder(Visitor::default()),
+    test_computed_forms,
+    // Input codes
+    r#"export default {
+        props: { first: String },
+        data() {
+            return { last: '' };
+        },
+        computed: {
+            full: {
+                get() {
+                    return this.first + ' ' + this.last;
+                },
+                set(value) {
+                    this.last = value;
+                },
+            },
+            upper: function () {
+                return this.full.toUpperCase();
+            },
+            lower: (vm) => vm.full.toLowerCase(),
+            initial: { get: (vm) => vm.first[0] },
+        },
+    };"#,
+    // Output codes after transformed with plugin
+    r#"import { defineComponent, ref, computed } from '@vue/composition-api';
+    export default defineComponent({
+        props: { first: String },
+        setup (props, ctx) {
+            const last = ref('');
+            const full = computed({
+                get: () => props.first + ' ' + last.value,
+                set: (value) => {
+                    last.value = value;
+                },
+            });
+            const upper = computed(() => full.value.toUpperCase());
+            const lower = computed(() => full.value.toLowerCase());
+            const initial = computed(() => props.first[0]);
+
+            return {
+                last,
+                full,
+                upper,
+                lower,
+                initial,
+            }
+        },
+    });"#
+);
+
+#[test]
+fn test_computed_arrow_this() {
+    let source = "export default { computed: { total: () => this.count } };";
+
+    let conversion = convert(source, &Options::default()).unwrap();
+    assert!(conversion
+        .output
+        .contains("const total = computed(()=>this.count);"));
+    assert_eq!(conversion.diagnostics.len(), 1);
+    assert_eq!(conversion.diagnostics[0].code, "unbound-this");
+}
+
+#[test]
+fn test_computed_passthrough() {
+    let source = r#"export default {
+        computed: {
+            ...mapGetters(['user']),
+            name() {
+                return this.user.name;
+            },
+        },
+    };"#;
+
+    let conversion = convert(source, &Options::default()).unwrap();
+    assert!(conversion
+        .output
+        .contains("computed: {\n        ...mapGetters(["));
+    assert!(conversion.output.contains("const name = computed(()=>"));
+    assert!(conversion
+        .diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic.code == "option-passed-through"));
+}
@@ -0,0 +1,127 @@
+use otc::diagnostics::Severity;
+use otc::visitor::Visitor;
+use otc::{convert, Options, Target};
+use swc_core::testing_transform::test;
+use swc_ecma_visit::as_folder;
+
+test!(
+    Default::default(),
+    |_| as_folder(Visitor::default()),
+    test_context,
+    // Input codes
+    r#"export default {
+        methods: {
+            run() {
+                this.$emit('run', this.$attrs.id);
+                return this.$scopedSlots.item || this.$listeners.click;
+            },
+        },
+    };"#,
+    // Output codes after transformed with plugin
+    r#"import { defineComponent } from '@vue/composition-api';
+    export default defineComponent({
+        setup (props, ctx) {
+            function run() {
+                ctx.emit('run', ctx.attrs.id);
+                return ctx.slots.item || ctx.listeners.click;
+            }
+
+            return {
+                run,
+            }
+        },
+    });"#
+);
+
+#[test]
+fn test_destructure_context() {
+    let source = r#"export default {
+        methods: {
+            run() {
+                this.$emit('run', this.$attrs.id);
+                this.$router.push('/');
+            },
+        },
+    };"#;
+
+    let options = Options {
+        destructure_context: true,
+        ..Default::default()
+    };
+    let conversion = convert(source, &options).unwrap();
+    assert!(conversion
+        .output
+        .contains("setup (props, { emit , attrs , root  })"));
+    assert!(conversion.output.contains("emit('run', attrs.id);"));
+    assert!(conversion.output.contains("root.$router.push('/');"));
+}
+
+#[test]
+fn test_destructure_unused_context() {
+    let source = "export default { methods: { run() { return 1; } } };";
+
+    let options = Options {
+        destructure_context: true,
+        ..Default::default()
+    };
+    let conversion = convert(source, &options).unwrap();
+    assert!(conversion.output.contains("setup (props) {"));
+}
+
+#[test]
+fn test_vue3_listeners() {
+    let source = "export default { methods: { run() { return this.$listeners; } } };";
+
+    let options = Options {
+        target: Target::Vue3,
+        ..Default::default()
+    };
+    let conversion = convert(source, &options).unwrap();
+    assert!(conversion.output.contains("return ctx.attrs;"));
+    assert_eq!(conversion.diagnostics.len(), 1);
+    assert_eq!(conversion.diagnostics[0].code, "listeners-in-attrs");
+    assert_eq!(conversion.diagnostics[0].severity, Severity::Warning);
+
+    // A listener read by its event name is the `onX` property of attrs
+    let source = "export default { methods: { run() { this.$listeners.click(); } } };";
+    let conversion = convert(source, &options).unwrap();
+    assert!(conversion.output.contains("ctx.attrs.onClick();"));
+    assert!(conversion.diagnostics.is_empty());
+}
+
+#[test]
+fn test_vue2_slots() {
+    let source = "export default { methods: { run() { return this.$slots.default; } } };";
+
+    let conversion = convert(source, &Options::default()).unwrap();
+    assert!(conversion.output.contains("return ctx.slots.default;"));
+    assert_eq!(conversion.diagnostics.len(), 1);
+    assert_eq!(conversion.diagnostics[0].code, "slots-are-functions");
+}
+
+#[test]
+fn test_script_setup_context() {
+    let source = r#"<script>
+export default {
+    methods: {
+        run() {
+            return this.$attrs.id;
+        },
+    },
+};
+</script>
+"#;
+
+    let options = Options {
+        sfc: true,
+        target: Target::Vue3,
+        script_setup: true,
+        ..Default::default()
+    };
+    let conversion = convert(source, &options).unwrap();
+    assert!(conversion
+        .output
+        .contains("import { useAttrs } from 'vue';"));
+    assert!(conversion.output.contains("const attrs = useAttrs();"));
+    assert!(conversion.output.contains("return attrs.id;"));
+}
@@ -1,4 +1,5 @@
 use otc::visitor::Visitor;
+use otc::{convert, Options, OtcError};
 use swc_core::testing_transform::test;
 use swc_ecma_visit::as_folder;
 
@@ -27,7 +28,8 @@ test!(
         },
     };"#,
     // Output codes after transformed with plugin
-    r#"export default defineComponent({
+    r#"import { defineComponent, ref } from '@vue/composition-api';
+    export default defineComponent({
         setup (props, ctx) {
             const loading = ref(false);
             const foo = ref(null);
@@ -52,3 +54,124 @@ test!(
         },
     });"#
 );
+
+test!(
+    Default::default(),
+    |_| as_folder(Visitor::default()),
+    test_data_function_value,
+    // Input codes
+    r#"export default {
+        data: function () {
+            return { count: 0 };
+        },
+        methods: {
+            increment() {
+                this.count++;
+            },
+        },
+    };"#,
+    // Output codes after transformed with plugin
+    r#"import { defineComponent, ref } from '@vue/composition-api';
+    export default defineComponent({
+        setup (props, ctx) {
+            const count = ref(0);
+            function increment() {
+                count.value++;
+            }
+
+            return {
+                count,
+                increment,
+            }
+        },
+    });"#
+);
+
+test!(
+    Default::default(),
+    |_| as_folder(Visitor::default()),
+    test_data_arrow_value,
+    // Input codes
+    r#"export default {
+        props: ['initial'],
+        data: (vm) => ({ count: vm.initial }),
+        methods: {
+            reset() {
+                this.count = this.initial;
+            },
+        },
+    };"#,
+    // Output codes after transformed with plugin
+    r#"import { defineComponent, ref } from '@vue/composition-api';
+    export default defineComponent({
+        props: ['initial'],
+        setup (props, ctx) {
+            const count = ref(props.initial);
+            function reset() {
+                count.value = props.initial;
+            }
+
+            return {
+                count,
+                reset,
+            }
+        },
+    });"#
+);
+
+test!(
+    Default::default(),
+    |_| as_folder(Visitor::default()),
+    test_data_statements_before_return,
+    // Input codes
+    r#"export default {
+        props: ['start'],
+        data() {
+            const base = this.start * 2;
+            return { count: base };
+        },
+    };"#,
+    // Output codes after transformed with plugin
+    r#"import { defineComponent, ref } from '@vue/composition-api';
+    export default defineComponent({
+        props: ['start'],
+        setup (props, ctx) {
+            const base = props.start * 2;
+            const count = ref(base);
+
+            return {
+                base,
+                count,
+            }
+        },
+    });"#
+);
+
+#[test]
+fn test_data_return_comments() {
+    let source = r#"export default {
+    data() {
+        // The counters
+        return {
+            count: 0,
+        };
+    },
+};"#;
+
+    let output = convert(source, &Options::default()).unwrap().output;
+    assert!(output.contains("// The counters\n        const count = ref(0);"));
+}
+
+#[test]
+fn test_data_local_collision() {
+    let source = "export default { data() { const items = []; return { items: items }; } };";
+    let conversion = convert(source, &Options::default()).unwrap();
+    assert_eq!(conversion.diagnostics.len(), 1);
+    assert_eq!(conversion.diagnostics[0].code, "data-local-collision");
+
+    let source = "export default { data() { if (ok) { return { a: 1 }; } return { a: 2 }; } };";
+    assert!(matches!(
+        convert(source, &Options::default()),
+        Err(OtcError::Unsupported(_))
+    ));
+}
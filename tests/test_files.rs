@@ -0,0 +1,125 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use otc::files::{collect_files, InputFile, WalkOptions};
+
+fn setup_tree(name: &str) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("otc_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    for dir in ["src/components", "src/views", "node_modules/lib", "dist"] {
+        fs::create_dir_all(root.join(dir)).unwrap();
+    }
+    for file in [
+        "src/components/B.vue",
+        "src/components/A.vue",
+        "src/components/A.spec.js",
+        "src/components/helper.js",
+        "src/views/Home.vue",
+        "node_modules/lib/Lib.vue",
+        "dist/Built.vue",
+    ] {
+        fs::write(root.join(file), "").unwrap();
+    }
+    fs::write(root.join(".gitignore"), "node_modules/\n").unwrap();
+
+    root
+}
+
+fn relative(root: &Path, files: Vec<InputFile>) -> Vec<String> {
+    files
+        .iter()
+        .map(|f| {
+            assert_eq!(f.path, root.join(&f.relative));
+            f.relative.to_string_lossy().replace('\\', "/")
+        })
+        .collect()
+}
+
+#[test]
+fn test_collect_files_recursive() {
+    let root = setup_tree("recursive");
+    let options = WalkOptions {
+        recursive: true,
+        exclude: vec!["dist".into()],
+        ..Default::default()
+    };
+
+    let collected = collect_files(std::slice::from_ref(&root), &options).unwrap();
+    assert_eq!(
+        relative(&root, collected.files),
+        vec![
+            "src/components/A.vue",
+            "src/components/B.vue",
+            "src/views/Home.vue"
+        ]
+    );
+
+    let options = WalkOptions {
+        recursive: true,
+        include: vec!["src/components/**".into()],
+        exclude: vec!["**/*.spec.js".into()],
+        extensions: vec!["vue".into(), "js".into()],
+    };
+    let collected = collect_files(std::slice::from_ref(&root), &options).unwrap();
+    assert_eq!(
+        relative(&root, collected.files),
+        vec![
+            "src/components/A.vue",
+            "src/components/B.vue",
+            "src/components/helper.js"
+        ]
+    );
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn test_collect_files_not_recursive() {
+    let root = setup_tree("flat");
+    let file = root.join("dist/Built.vue");
+
+    let collected = collect_files(&[root.clone(), file.clone()], &Default::default()).unwrap();
+    assert_eq!(
+        collected.files,
+        vec![InputFile {
+            path: file,
+            relative: "Built.vue".into()
+        }]
+    );
+    assert_eq!(collected.skipped_dirs, vec![root.clone()]);
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn test_collect_files_explicit_relative() {
+    let paths: Vec<PathBuf> = vec!["a/X.vue".into(), "./b/X.vue".into(), "../X.vue".into()];
+
+    let collected = collect_files(&paths, &Default::default()).unwrap();
+    let relative: Vec<PathBuf> = collected.files.iter().map(|f| f.relative.clone()).collect();
+    assert_eq!(
+        relative,
+        vec![
+            PathBuf::from("a/X.vue"),
+            PathBuf::from("b/X.vue"),
+            PathBuf::from("X.vue")
+        ]
+    );
+    assert!(collected.collisions().is_empty());
+}
+
+#[test]
+fn test_collect_files_collisions() {
+    let root = setup_tree("collisions");
+    let paths = vec![
+        root.join("src/components/A.vue"),
+        root.join("dist/Built.vue"),
+        root.join("src/views/A.vue"),
+    ];
+
+    // Outside the current directory, only the file names are kept
+    let collected = collect_files(&paths, &Default::default()).unwrap();
+    assert_eq!(collected.collisions(), vec![Path::new("A.vue")]);
+
+    fs::remove_dir_all(root).unwrap();
+}
@@ -0,0 +1,118 @@
+use otc::visitor::Visitor;
+use otc::{convert, Options, Target};
+use swc_core::testing_transform::test;
+use swc_ecma_visit::as_folder;
+
+test!(
+    Default::default(),
+    |_| as_folder(Visitor::default()),
+    test_imports_added_after_existing,
+    // Input codes
+    r#"import Foo from './Foo.vue';
+    import { something } from 'somewhere';
+
+    export default {
+        data() {
+            return {
+                count: 0,
+            };
+        },
+        computed: {
+            double() {
+                return this.count * 2;
+            },
+        },
+    };"#,
+    // Output codes after transformed with plugin
+    r#"import Foo from './Foo.vue';
+    import { something } from 'somewhere';
+    import { defineComponent, ref, computed } from '@vue/composition-api';
+
+    export default defineComponent({
+        setup (props, ctx) {
+            const count = ref(0);
+            const double = computed(()=>count.value * 2);
+
+            return {
+                count,
+                double,
+            }
+        },
+    });"#
+);
+
+test!(
+    Default::default(),
+    |_| as_folder(Visitor::default()),
+    test_imports_merged_into_existing,
+    // Input codes
+    r#"import { ref, computed as c } from '@vue/composition-api';
+
+    export default {
+        data() {
+            return {
+                count: 0,
+            };
+        },
+        computed: {
+            double() {
+                return this.count * 2;
+            },
+        },
+    };"#,
+    // Output codes after transformed with plugin
+    r#"import { ref, computed as c, defineComponent, computed } from '@vue/composition-api';
+
+    export default defineComponent({
+        setup (props, ctx) {
+            const count = ref(0);
+            const double = computed(()=>count.value * 2);
+
+            return {
+                count,
+                double,
+            }
+        },
+    });"#
+);
+
+#[test]
+fn test_imports_already_bound_from_vue() {
+    let options = Options {
+        target: Target::Vue2_6,
+        ..Default::default()
+    };
+    let conversion = convert(
+        r#"import { defineComponent, ref } from 'vue';
+
+export default defineComponent({
+    data() {
+        return {
+            count: 0,
+        };
+    },
+});
+"#,
+        &options,
+    )
+    .unwrap();
+
+    assert_eq!(
+        conversion.output,
+        r#"import { defineComponent, ref } from 'vue';
+export default defineComponent({
+    setup (props, ctx) {
+        const count = ref(0);
+        return {
+            count
+        };
+    }
+});
+"#
+    );
+    assert_eq!(conversion.diagnostics.len(), 2);
+    assert!(conversion
+        .diagnostics
+        .iter()
+        .all(|diagnostic| diagnostic.code == "import-conflict"));
+}
@@ -23,7 +23,8 @@ test!(
         },
     };"#,
     // Output codes after transformed with plugin
-    r#"export default defineComponent({
+    r#"import { defineComponent, inject } from '@vue/composition-api';
+    export default defineComponent({
         setup (props, ctx) {
             const something = inject('something');
             const something2 = inject('something2', ()=>3);
@@ -0,0 +1,97 @@
+use otc::visitor::Visitor;
+use otc::{convert, Options, Target};
+use swc_core::testing_transform::test;
+use swc_ecma_visit::as_folder;
+
+test!(
+    Default::default(),
+    |_| as_folder(Visitor::default()),
+    test_instance_helpers,
+    // Input codes
+    r#"export default {
+        data() {
+            return { form: {} };
+        },
+        methods: {
+            async reset(key) {
+                this.$set(this.form, key, null);
+                this.$delete(this.form, 'error');
+                await this.$nextTick();
+            },
+        },
+    };"#,
+    // Output codes after transformed with plugin
+    r#"import { defineComponent, ref, set, del, nextTick } from '@vue/composition-api';
+    export default defineComponent({
+        setup (props, ctx) {
+            const form = ref({});
+
+            async function reset(key) {
+                set(form.value, key, null);
+                del(form.value, 'error');
+                await nextTick();
+            }
+
+            return {
+                form,
+                reset,
+            }
+        },
+    });"#
+);
+
+#[test]
+fn test_vue3_set_delete() {
+    let source = r#"export default {
+        data() {
+            return { form: {} };
+        },
+        methods: {
+            reset(key) {
+                this.$set(this.form, key, null);
+                this.$set(this.form, 'touched', true);
+                this.$delete(this.form, 'error');
+            },
+        },
+    };"#;
+
+    let options = Options {
+        target: Target::Vue3,
+        ..Default::default()
+    };
+    let conversion = convert(source, &options).unwrap();
+    assert!(conversion
+        .output
+        .contains("import { defineComponent, ref } from 'vue';"));
+    assert!(conversion.output.contains("form.value[key] = null;"));
+    assert!(conversion.output.contains("form.value.touched = true;"));
+    assert!(conversion.output.contains("delete form.value.error;"));
+    assert!(conversion.diagnostics.is_empty());
+}
+
+#[test]
+fn test_vue3_set_reference() {
+    let source = "export default { methods: { setter() { return this.$set; } } };";
+
+    let options = Options {
+        target: Target::Vue3,
+        ..Default::default()
+    };
+    let conversion = convert(source, &options).unwrap();
+    assert!(conversion.output.contains("return instance.$set;"));
+    assert_eq!(conversion.diagnostics.len(), 1);
+    assert_eq!(conversion.diagnostics[0].code, "no-set-delete");
+}
+
+#[test]
+fn test_force_update() {
+    let source = "export default { methods: { refresh() { this.$forceUpdate(); } } };";
+
+    let conversion = convert(source, &Options::default()).unwrap();
+    assert!(conversion
+        .output
+        .contains("const instance = getCurrentInstance().proxy;"));
+    assert!(conversion.output.contains("instance.$forceUpdate();"));
+    assert_eq!(conversion.diagnostics.len(), 1);
+    assert_eq!(conversion.diagnostics[0].code, "force-update");
+}
@@ -0,0 +1,101 @@
+use otc::visitor::Visitor;
+use otc::{convert, Options, Target};
+use swc_core::testing_transform::test;
+use swc_ecma_visit::as_folder;
+
+test!(
+    Default::default(),
+    |_| as_folder(Visitor::default()),
+    test_lifecycle_hooks,
+    // Input codes
+    r#"export default {
+        data() {
+            return { timer: null };
+        },
+        beforeCreate() {
+            console.log('starting');
+        },
+        mounted() {
+            this.timer = setInterval(this.poll, 1000);
+        },
+        beforeUpdate() {},
+        beforeDestroy() {
+            clearInterval(this.timer);
+        },
+        destroyed: function () {
+            this.timer = null;
+        },
+        errorCaptured(err, vm, info) {
+            return false;
+        },
+        methods: {
+            poll() {},
+        },
+    };"#,
+    // Output codes after transformed with plugin
+    r#"import { defineComponent, ref, onMounted, onBeforeUpdate, onBeforeUnmount, onUnmounted, onErrorCaptured } from '@vue/composition-api';
+    export default defineComponent({
+        setup (props, ctx) {
+            const timer = ref(null);
+            console.log('starting');
+
+            function poll() {}
+
+            onMounted(() => {
+                timer.value = setInterval(poll, 1000);
+            });
+            onBeforeUpdate(() => {});
+            onBeforeUnmount(() => {
+                clearInterval(timer.value);
+            });
+            onUnmounted(() => {
+                timer.value = null;
+            });
+            onErrorCaptured((err, vm, info) => {
+                return false;
+            });
+
+            return {
+                timer,
+                poll,
+            }
+        },
+    });"#
+);
+
+#[test]
+fn test_handled_hooks() {
+    let source = r#"export default {
+        activated() {},
+        created() {},
+        beforeCreate() {},
+        serverPrefetch() {},
+    };"#;
+
+    let conversion = convert(source, &Options::default()).unwrap();
+    assert_eq!(
+        conversion.handled,
+        vec!["beforeCreate", "created", "activated", "serverPrefetch"]
+    );
+}
+
+#[test]
+fn test_vue3_hook_names() {
+    let source = "export default { beforeUnmount() {}, unmounted() {} };";
+
+    let vue2 = convert(source, &Options::default()).unwrap();
+    assert_eq!(vue2.diagnostics.len(), 2);
+    assert!(vue2
+        .diagnostics
+        .iter()
+        .all(|diagnostic| diagnostic.code == "hook-not-in-target"));
+
+    let options = Options {
+        target: Target::Vue3,
+        ..Default::default()
+    };
+    let vue3 = convert(source, &options).unwrap();
+    assert!(vue3.output.contains("onBeforeUnmount(()=>{});"));
+    assert!(vue3.output.contains("onUnmounted(()=>{});"));
+    assert!(vue3.diagnostics.is_empty());
+}
@@ -37,7 +37,8 @@ test!(
             },
         },
     };"#,
-    r#"export default defineComponent({
+    r#"import { defineComponent, ref } from '@vue/composition-api';
+    export default defineComponent({
         props: {
             something: {
                 type: String,
@@ -51,8 +52,8 @@ test!(
                 x.value = 2;
                 props.something.foo.bar.run();
                 console.log(props.something);
-                ctx.$emit('foo');
-                ctx.$root.foo;
+                ctx.emit('foo');
+                ctx.root.$foo;
             }
 
             return {
@@ -0,0 +1,72 @@
+use otc::visitor::Visitor;
+use otc::{convert, Options};
+use swc_core::testing_transform::test;
+use swc_ecma_visit::as_folder;
+
+test!(
+    Default::default(),
+    |_| as_folder(Visitor::default()),
+    test_methods_function_values,
+    // Input codes
+    r#"export default {
+        data() {
+            return { count: 0 };
+        },
+        methods: {
+            increment: function (step) {
+                this.count += step;
+            },
+            reset: () => 0,
+        },
+    };"#,
+    // Output codes after transformed with plugin
+    r#"import { defineComponent, ref } from '@vue/composition-api';
+    export default defineComponent({
+        setup (props, ctx) {
+            const count = ref(0);
+            function increment(step) {
+                count.value += step;
+            }
+            function reset() {
+                return 0;
+            }
+
+            return {
+                count,
+                increment,
+                reset,
+            }
+        },
+    });"#
+);
+
+#[test]
+fn test_methods_passthrough() {
+    let source = r#"export default {
+        methods: {
+            ...mapActions(['fetch']),
+            save: debounce(function () {
+                this.fetch();
+            }, 300),
+            run() {
+                this.save();
+            },
+        },
+    };"#;
+
+    let conversion = convert(source, &Options::default()).unwrap();
+    assert!(conversion
+        .output
+        .contains("methods: {\n        ...mapActions(["));
+    assert!(conversion
+        .output
+        .contains("save: debounce(function() {\n            this.fetch();"));
+    assert!(conversion
+        .output
+        .contains("function run() {\n            instance.save();"));
+    assert_eq!(conversion.diagnostics.len(), 2);
+    assert!(conversion
+        .diagnostics
+        .iter()
+        .all(|diagnostic| diagnostic.code == "option-passed-through"));
+}
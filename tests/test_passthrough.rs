@@ -0,0 +1,81 @@
+use otc::visitor::Visitor;
+use otc::{convert, Options, Target};
+use swc_core::testing_transform::test;
+use swc_ecma_visit::as_folder;
+
+test!(
+    Default::default(),
+    |_| as_folder(Visitor::default()),
+    test_passthrough,
+    // Input codes
+    r#"export default {
+        name: 'Form',
+        mixins: [formMixin],
+        ...shared,
+        data() {
+            return { title: 'Form' };
+        },
+        metaInfo() {
+            return { title: this.title };
+        },
+    };"#,
+    // Output codes after transformed with plugin
+    r#"import { defineComponent, ref } from '@vue/composition-api';
+    export default defineComponent({
+        name: 'Form',
+        mixins: [formMixin],
+        ...shared,
+        metaInfo() {
+            return { title: this.title };
+        },
+        setup (props, ctx) {
+            const title = ref('Form');
+
+            return {
+                title,
+            }
+        },
+    });"#
+);
+
+#[test]
+fn test_passthrough_diagnostics() {
+    let source = r#"export default {
+        directives: { focus },
+        computed: mapGetters(['user']),
+        methods: {
+            run() {},
+        },
+    };"#;
+
+    let conversion = convert(source, &Options::default()).unwrap();
+    assert!(conversion.output.contains("computed: mapGetters(["));
+    assert_eq!(conversion.handled, vec!["methods"]);
+    assert_eq!(conversion.diagnostics.len(), 2);
+    assert!(conversion
+        .diagnostics
+        .iter()
+        .all(|diagnostic| diagnostic.code == "option-passed-through"));
+    assert!(conversion.diagnostics[0].message.contains("`directives`"));
+}
+
+#[test]
+fn test_script_setup_passthrough() {
+    let source = r#"<script>
+export default {
+    name: 'Form',
+    mixins: [formMixin],
+};
+</script>
+"#;
+
+    let options = Options {
+        sfc: true,
+        target: Target::Vue3,
+        script_setup: true,
+        ..Default::default()
+    };
+    let conversion = convert(source, &options).unwrap();
+    assert!(conversion.output.contains("defineOptions({"));
+    assert!(conversion.output.contains("mixins: ["));
+}
@@ -0,0 +1,105 @@
+use otc::{convert, Options, OtcError};
+
+#[test]
+fn test_convert_without_component_is_unchanged() {
+    let source = r#"import { defineComponent } from '@vue/composition-api';
+
+export default defineComponent({
+    setup() {
+        return {};
+    },
+});
+"#;
+
+    let conversion = convert(source, &Options::default()).unwrap();
+    assert!(!conversion.converted);
+    assert_eq!(conversion.output, source);
+}
+
+#[test]
+fn test_convert_without_setup_options_is_unchanged() {
+    let source = "export default { name: 'Foo', inheritAttrs: false };\n";
+    let conversion = convert(source, &Options::default()).unwrap();
+    assert!(!conversion.converted);
+    assert_eq!(conversion.output, source);
+
+    // The `<script>` next to `<script setup>` only adds options to it
+    let source = "<script>\nexport default { inheritAttrs: false, created() {} };\n</script>\n<script setup>\nconst a = 1;\n</script>\n";
+    let sfc = Options {
+        sfc: true,
+        ..Default::default()
+    };
+    let conversion = convert(source, &sfc).unwrap();
+    assert!(!conversion.converted);
+    assert_eq!(conversion.output, source);
+}
+
+#[test]
+fn test_convert_errors() {
+    let sfc = Options {
+        sfc: true,
+        ..Default::default()
+    };
+
+    assert!(matches!(
+        convert("<template><div /></template>", &sfc),
+        Err(OtcError::NoScript(_))
+    ));
+    assert!(matches!(
+        convert("export default {", &Options::default()),
+        Err(OtcError::Parse(_))
+    ));
+    assert!(matches!(
+        convert(
+            "export default { data() { return makeData(); } };",
+            &Options::default()
+        ),
+        Err(OtcError::Unsupported(_))
+    ));
+    assert!(matches!(
+        convert(
+            "export default { data() { return { ...defaults }; } };",
+            &Options::default()
+        ),
+        Err(OtcError::Unsupported(_))
+    ));
+}
+
+#[test]
+fn test_convert_diagnostics() {
+    let options = Options {
+        sfc: true,
+        filename: Some("Foo.vue".into()),
+        ..Default::default()
+    };
+    let source = "<template>\n  <div />\n</template>\n\n<script>\nexport default {\n    data() {\n        return {\n            loading: false,\n            test,\n        };\n    },\n};\n</script>\n";
+
+    let conversion = convert(source, &options).unwrap();
+    assert_eq!(conversion.diagnostics.len(), 1);
+    assert_eq!(
+        conversion.diagnostics[0].to_string(),
+        "Foo.vue:10:13: warning[data-property-skipped]: property returned from data() was not converted to a ref"
+    );
+
+    match convert(
+        "<script>\nexport default {\n  data() {\n    return { a: };\n  },\n};\n</script>",
+        &options,
+    ) {
+        Err(OtcError::Parse(diagnostic)) => {
+            let range = diagnostic.range.unwrap();
+            assert_eq!((range.start.line, range.start.column), (4, 17));
+        }
+        res => panic!("expected parse error, got {:?}", res),
+    }
+}
+
+#[test]
+#[allow(deprecated)]
+fn test_process_wraps_convert() {
+    let source = "export default { data() { return { count: 0 }; } };";
+    assert_eq!(
+        otc::process(source.into()),
+        convert(source, &Options::default()).unwrap().output
+    );
+    assert_eq!(otc::process("export default {".into()), "");
+}
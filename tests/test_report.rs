@@ -0,0 +1,79 @@
+use std::path::Path;
+
+use otc::report::{FileStatus, Report};
+use otc::{convert, Options};
+use serde_json::Value;
+
+#[test]
+fn test_report() {
+    let options = Options::default();
+    let mut report = Report::default();
+    report.push(
+        Path::new("src/Converted.js"),
+        &convert(
+            "export default { props: ['a'], data() { return { b: 1, c } } };",
+            &options,
+        ),
+    );
+    report.push(
+        Path::new("src/Skipped.js"),
+        &convert("export const a = 1;", &options),
+    );
+    report.push(
+        Path::new("src/Failed.js"),
+        &convert("export default {", &options),
+    );
+
+    assert_eq!(report.count(FileStatus::Converted), 1);
+    assert_eq!(report.count(FileStatus::Skipped), 1);
+    assert_eq!(report.count(FileStatus::Failed), 1);
+
+    // Components with no script to convert are skipped, malformed ones fail to parse
+    let sfc = Options {
+        sfc: true,
+        ..Default::default()
+    };
+    let mut components = Report::default();
+    components.push(
+        Path::new("src/Template.vue"),
+        &convert("<template><div /></template>", &sfc),
+    );
+    components.push(
+        Path::new("src/Setup.vue"),
+        &convert("<script setup>\nconst a = 1;\n</script>", &sfc),
+    );
+    components.push(
+        Path::new("src/Malformed.vue"),
+        &convert("<template><div /></template>\n<script>", &sfc),
+    );
+    assert_eq!(components.count(FileStatus::Skipped), 2);
+    assert_eq!(components.files[2].status, FileStatus::Failed);
+    assert_eq!(
+        components.files[2].error.as_ref().unwrap().code,
+        "parse-error"
+    );
+    let range = components.files[2].diagnostics[0].range.unwrap();
+    assert_eq!((range.start.line, range.start.column), (2, 1));
+
+    let json: Value = serde_json::from_str(&report.to_json()).unwrap();
+    assert_eq!(json["summary"]["converted"], 1);
+    assert_eq!(json["files"][0]["status"], "converted");
+    assert_eq!(
+        json["files"][0]["handled"],
+        serde_json::json!(["props", "data"])
+    );
+    assert_eq!(json["files"][2]["status"], "failed");
+    assert_eq!(json["files"][2]["error"]["code"], "parse-error");
+
+    let sarif: Value = serde_json::from_str(&report.to_sarif()).unwrap();
+    let results = sarif["runs"][0]["results"].as_array().unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0]["ruleId"], "data-property-skipped");
+    assert_eq!(results[0]["level"], "warning");
+    assert_eq!(
+        results[0]["locations"][0]["physicalLocation"]["region"]["startColumn"],
+        56
+    );
+    assert_eq!(results[1]["ruleId"], "parse-error");
+    assert_eq!(results[1]["level"], "error");
+}
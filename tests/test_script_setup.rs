@@ -0,0 +1,184 @@
+use otc::{convert, Options, OtcError, Target};
+
+fn script_setup(target: Target, sfc: bool) -> Options {
+    Options {
+        sfc,
+        target,
+        script_setup: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_script_setup_output() {
+    let source = r#"<template>
+    <Foo :count="count" />
+</template>
+
+<script lang="js">
+import Foo from './Foo.vue';
+import Bar from './Bar.vue';
+
+export default {
+    name: 'Counter',
+    inheritAttrs: false,
+    components: { Foo, Baz: Bar },
+    // The props
+    props: { step: Number },
+    data() {
+        return { count: 0 };
+    },
+    methods: {
+        inc() {
+            this.count += this.step;
+            this.$emit('changed', this.count);
+        },
+    },
+};
+</script>
+"#;
+
+    let conversion = convert(source, &script_setup(Target::Vue3, true)).unwrap();
+    assert_eq!(
+        conversion.output,
+        r#"<template>
+    <Foo :count="count" />
+</template>
+
+<script setup lang="js">
+import Foo from './Foo.vue';
+import Bar from './Bar.vue';
+import { ref } from 'vue';
+// The props
+const props = defineProps({
+    step: Number
+});
+const emit = defineEmits([
+    'changed'
+]);
+defineOptions({
+    name: 'Counter',
+    inheritAttrs: false
+});
+const count = ref(0);
+function inc() {
+    count.value += props.step;
+    emit('changed', count.value);
+}
+</script>
+"#
+    );
+
+    // `Baz` is registered under another name than it's imported as
+    assert_eq!(conversion.diagnostics.len(), 1);
+    assert_eq!(conversion.diagnostics[0].code, "component-not-registered");
+}
+
+#[test]
+fn test_script_setup_declared_emits() {
+    let source = r#"<script>
+export default {
+    emits: ['update'],
+    methods: {
+        save(event) {
+            this.$emit(event);
+        },
+    },
+};
+</script>
+"#;
+
+    let conversion = convert(source, &script_setup(Target::Vue2_7, true)).unwrap();
+    assert!(conversion.diagnostics.is_empty());
+    assert_eq!(
+        conversion.output,
+        r#"<script setup>
+const emit = defineEmits([
+    'update'
+]);
+function save(event) {
+    emit(event);
+}
+</script>
+"#
+    );
+}
+
+#[test]
+fn test_script_setup_plain_script() {
+    let source = r#"<script>
+// The sizes to pick from
+export const SIZES = ['s', 'm'];
+
+export default {
+    name: 'Picker',
+    data() {
+        return { size: SIZES[0] };
+    },
+};
+</script>
+"#;
+
+    // Named exports can't be in `<script setup>`, and Vue 2.7 has no `defineOptions`
+    let conversion = convert(source, &script_setup(Target::Vue2_7, true)).unwrap();
+    assert_eq!(
+        conversion.output,
+        r#"<script>
+// The sizes to pick from
+export const SIZES = [
+    's',
+    'm'
+];
+export default {
+    name: 'Picker'
+};
+</script>
+
+<script setup>
+import { ref } from 'vue';
+const size = ref(SIZES[0]);
+</script>
+"#
+    );
+
+    let conversion = convert(source, &script_setup(Target::Vue3, true)).unwrap();
+    assert_eq!(
+        conversion.output,
+        r#"<script>
+// The sizes to pick from
+export const SIZES = [
+    's',
+    'm'
+];
+</script>
+
+<script setup>
+import { ref } from 'vue';
+defineOptions({
+    name: 'Picker'
+});
+const size = ref(SIZES[0]);
+</script>
+"#
+    );
+}
+
+#[test]
+fn test_script_setup_errors() {
+    let source = "<script>\nexport default {};\n</script>\n";
+    assert!(matches!(
+        convert(source, &script_setup(Target::Vue2_6, true)),
+        Err(OtcError::Unsupported(_))
+    ));
+    assert!(matches!(
+        convert("export default {};", &script_setup(Target::Vue3, false)),
+        Err(OtcError::Unsupported(_))
+    ));
+    assert!(matches!(
+        convert(
+            "<script>\nexport default {};\n</script>\n<script setup>\nconst a = 1;\n</script>\n",
+            &script_setup(Target::Vue3, true)
+        ),
+        Err(OtcError::Unsupported(_))
+    ));
+}
@@ -0,0 +1,139 @@
+use otc::parser::{find_script_block, InvalidScriptError};
+use otc::sfc::{parse_sfc, replace_block_content, SfcError};
+use otc::{convert, Options};
+
+const COMPONENT: &str = r#"<!-- A <script> in a comment -->
+<template>
+    <div>
+        <template v-if="ok"><span>"<script>"</span></template>
+    </div>
+</template>
+
+<script lang="ts">
+export default { name: 'Foo', created() {} };
+</script>
+
+<script setup>
+const a = 1;
+</script>
+
+<style scoped lang='scss'>
+.a { color: red; }
+</style>
+
+<i18n locale=en>
+{ "hello": "Hello" }
+</i18n>
+"#;
+
+#[test]
+fn test_sfc_blocks() {
+    let sfc = parse_sfc(COMPONENT).unwrap();
+    let names: Vec<&str> = sfc.blocks.iter().map(|b| b.name.as_str()).collect();
+    assert_eq!(names, vec!["template", "script", "script", "style", "i18n"]);
+
+    // Nested templates don't end the template block
+    let template = sfc.template().unwrap();
+    assert!(template.content_of(COMPONENT).contains("</template>"));
+    assert!(COMPONENT[template.range.clone()].ends_with("</div>\n</template>"));
+
+    let script = sfc.script().unwrap();
+    assert_eq!(script.lang(), Some("ts"));
+    assert_eq!(
+        script.content_of(COMPONENT),
+        "\nexport default { name: 'Foo', created() {} };\n"
+    );
+
+    let setup = sfc.script_setup().unwrap();
+    assert!(setup.is_setup());
+    assert_eq!(setup.content_of(COMPONENT), "\nconst a = 1;\n");
+
+    let style = sfc.styles().next().unwrap();
+    assert!(style.attr("scoped").is_some());
+    assert_eq!(style.lang(), Some("scss"));
+
+    let custom: Vec<_> = sfc.custom_blocks().collect();
+    assert_eq!(custom.len(), 1);
+    assert_eq!(custom[0].attr_value("locale"), Some("en"));
+}
+
+#[test]
+fn test_sfc_errors() {
+    assert_eq!(
+        parse_sfc("<template><div /></template>\n<script>\nfoo"),
+        Err(SfcError::UnclosedBlock("script".into(), 29))
+    );
+    assert_eq!(
+        parse_sfc("</script>"),
+        Err(SfcError::UnexpectedClosingTag("script".into(), 0))
+    );
+
+    let sfc = parse_sfc(r#"<script src="./foo.js" />"#).unwrap();
+    assert!(matches!(
+        find_script_block(&sfc),
+        Err(InvalidScriptError::ExternalSource(src)) if src == "./foo.js"
+    ));
+
+    let sfc = parse_sfc("<template><div /></template>").unwrap();
+    assert!(matches!(
+        find_script_block(&sfc),
+        Err(InvalidScriptError::NoScript)
+    ));
+}
+
+#[test]
+fn test_replace_block_content() {
+    let source = "<template>\r\n  <div />\r\n</template>\r\n<script>\r\n  const a = 1;\r\n</script>\r\n<style>\r\n</style>\r\n";
+    let sfc = parse_sfc(source).unwrap();
+    let script = sfc.script().unwrap();
+
+    assert_eq!(
+        replace_block_content(source, script, "const a = 2;\n\nconst b = 3;\n", &[]),
+        "<template>\r\n  <div />\r\n</template>\r\n<script>\r\n  const a = 2;\r\n\r\n  const b = 3;\r\n</script>\r\n<style>\r\n</style>\r\n"
+    );
+}
+
+#[test]
+fn test_process_sfc_keeps_template_literals() {
+    let options = Options {
+        sfc: true,
+        ..Default::default()
+    };
+    let source = "<script>\n  export default {\n    methods: {\n      message() {\n        return `first\nsecond\n  third`;\n      },\n    },\n  };\n</script>\n";
+    let converted = convert(source, &options).unwrap().output;
+
+    assert!(converted.contains("  function message() {\n"));
+    assert!(converted.contains("return `first\nsecond\n  third`;\n"));
+}
+
+#[test]
+fn test_process_sfc_preserves_blocks() {
+    let options = Options {
+        sfc: true,
+        ..Default::default()
+    };
+    let converted = convert(
+        COMPONENT
+            .replace("<script setup>\nconst a = 1;\n</script>\n", "")
+            .as_str(),
+        &options,
+    )
+    .unwrap()
+    .output;
+    let sfc = parse_sfc(&converted).unwrap();
+    let original = parse_sfc(COMPONENT).unwrap();
+
+    assert_eq!(
+        sfc.template().unwrap().content_of(&converted),
+        original.template().unwrap().content_of(COMPONENT)
+    );
+    assert_eq!(
+        &converted[sfc.styles().next().unwrap().range.start..],
+        &COMPONENT[original.styles().next().unwrap().range.start..]
+    );
+    assert!(sfc
+        .script()
+        .unwrap()
+        .content_of(&converted)
+        .starts_with("\nimport { defineComponent } from '@vue/composition-api';\nexport default defineComponent({"));
+}
@@ -0,0 +1,86 @@
+use otc::{convert, Options, Target};
+
+const COMPONENT: &str = r#"import { ref } from 'vue';
+
+export default {
+    data() {
+        return {
+            count: 0,
+        };
+    },
+    methods: {
+        go() {
+            this.$router.push({ query: { count: this.count } });
+        },
+    },
+};
+"#;
+
+fn convert_for(target: Target) -> String {
+    let options = Options {
+        target,
+        ..Default::default()
+    };
+
+    convert(COMPONENT, &options).unwrap().output
+}
+
+#[test]
+fn test_target_parse() {
+    assert_eq!("2.6".parse(), Ok(Target::Vue2_6));
+    assert_eq!("vue2.7".parse(), Ok(Target::Vue2_7));
+    assert_eq!("3".parse(), Ok(Target::Vue3));
+    assert!("4".parse::<Target>().is_err());
+}
+
+#[test]
+fn test_target_vue2_6() {
+    assert_eq!(
+        convert_for(Target::Vue2_6),
+        r#"import { ref } from 'vue';
+import { defineComponent } from '@vue/composition-api';
+export default defineComponent({
+    setup (props, ctx) {
+        const count = ref(0);
+        function go() {
+            ctx.root.$router.push({
+                query: {
+                    count: count.value
+                }
+            });
+        }
+        return {
+            count,
+            go
+        };
+    }
+});
+"#
+    );
+}
+
+#[test]
+fn test_target_vue3() {
+    assert_eq!(
+        convert_for(Target::Vue3),
+        r#"import { ref, defineComponent, getCurrentInstance } from 'vue';
+export default defineComponent({
+    setup (props, ctx) {
+        const instance = getCurrentInstance().proxy;
+        const count = ref(0);
+        function go() {
+            instance.$router.push({
+                query: {
+                    count: count.value
+                }
+            });
+        }
+        return {
+            count,
+            go
+        };
+    }
+});
+"#
+    );
+}
@@ -0,0 +1,117 @@
+use otc::visitor::Visitor;
+use otc::{convert, Options};
+use swc_core::testing_transform::test;
+use swc_ecma_visit::as_folder;
+
+test!(
+    Default::default(),
+    |_| as_folder(Visitor::default()),
+    test_template_refs,
+    // Input codes
+    r#"export default {
+        methods: {
+            focus() {
+                this.$refs.input.focus();
+                this.$refs.list.scrollTop = 0;
+            },
+            blur() {
+                this.$refs.input.blur();
+            },
+        },
+    };"#,
+    // Output codes after transformed with plugin
+    r#"import { defineComponent, ref } from '@vue/composition-api';
+    export default defineComponent({
+        setup (props, ctx) {
+            const input = ref(null);
+            const list = ref(null);
+
+            function focus() {
+                input.value.focus();
+                list.value.scrollTop = 0;
+            }
+            function blur() {
+                input.value.blur();
+            }
+
+            return {
+                input,
+                list,
+                focus,
+                blur,
+            }
+        },
+    });"#
+);
+
+#[test]
+fn test_template_ref_collision() {
+    let source = r#"export default {
+        data() {
+            return { form: {} };
+        },
+        methods: {
+            validate() {
+                return this.$refs.form.validate(this.form);
+            },
+        },
+    };"#;
+
+    let conversion = convert(source, &Options::default()).unwrap();
+    assert!(conversion.output.contains("const formRef = ref(null);"));
+    assert!(conversion
+        .output
+        .contains("return formRef.value.validate(form.value);"));
+    assert_eq!(conversion.diagnostics.len(), 1);
+    assert_eq!(conversion.diagnostics[0].code, "template-ref-renamed");
+}
+
+#[test]
+fn test_template_ref_shadowed() {
+    let source = r#"export default {
+        methods: {
+            validate() {
+                const form = this.$refs.form;
+                return form.validate();
+            },
+            reset() {
+                this.$refs.form.reset();
+            },
+        },
+    };"#;
+
+    let conversion = convert(source, &Options::default()).unwrap();
+    assert!(conversion.output.contains("const formRef = ref(null);"));
+    assert!(conversion.output.contains("const form = formRef.value;"));
+    assert!(conversion.output.contains("formRef.value.reset();"));
+    assert_eq!(conversion.diagnostics.len(), 1);
+    assert_eq!(conversion.diagnostics[0].code, "template-ref-renamed");
+
+    // Once declared, the ref can still be shadowed somewhere else
+    let source = r#"export default {
+        methods: {
+            reset() {
+                this.$refs.form.reset();
+            },
+            validate() {
+                const form = this.$refs.form;
+                return form.validate();
+            },
+        },
+    };"#;
+
+    let conversion = convert(source, &Options::default()).unwrap();
+    assert!(conversion
+        .diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic.code == "shadowed-member"));
+}
+
+#[test]
+fn test_dynamic_template_ref() {
+    let source = "export default { methods: { get(name) { return this.$refs[name]; } } };";
+
+    let conversion = convert(source, &Options::default()).unwrap();
+    assert!(conversion.output.contains("return instance.$refs[name];"));
+    assert!(!conversion.output.contains("ref(null)"));
+}
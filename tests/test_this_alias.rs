@@ -0,0 +1,208 @@
+use otc::visitor::Visitor;
+use otc::{convert, Options};
+use swc_core::testing_transform::test;
+use swc_ecma_visit::as_folder;
+
+test!(
+    Default::default(),
+    |_| as_folder(Visitor::default()),
+    test_this_alias,
+    // Input codes
+    r#"export default {
+        data() {
+            return { loading: false };
+        },
+        methods: {
+            load() {
+                const self = this;
+                self.loading = true;
+                fetchItems(function (result) {
+                    self.loading = false;
+                    self.$emit('loaded', result);
+                });
+            },
+        },
+    };"#,
+    // Output codes after transformed with plugin
+    r#"import { defineComponent, ref } from '@vue/composition-api';
+    export default defineComponent({
+        setup (props, ctx) {
+            const loading = ref(false);
+
+            function load() {
+                loading.value = true;
+                fetchItems(function (result) {
+                    loading.value = false;
+                    ctx.emit('loaded', result);
+                });
+            }
+
+            return {
+                loading,
+                load,
+            }
+        },
+    });"#
+);
+
+test!(
+    Default::default(),
+    |_| as_folder(Visitor::default()),
+    test_this_destructure,
+    // Input codes
+    r#"export default {
+        props: { size: Number },
+        data() {
+            return { items: [] };
+        },
+        methods: {
+            summary() {
+                const { items, size: max, $el } = this;
+                return { items, max, height: $el.clientHeight };
+            },
+        },
+    };"#,
+    // Output codes after transformed with plugin
+    r#"import { defineComponent, getCurrentInstance, ref } from '@vue/composition-api';
+    export default defineComponent({
+        props: { size: Number },
+        setup (props, ctx) {
+            const instance = getCurrentInstance().proxy;
+            const items = ref([]);
+
+            function summary() {
+                const max = props.size, $el = instance.$el;
+                return { items: items.value, max, height: $el.clientHeight };
+            }
+
+            return {
+                items,
+                summary,
+            }
+        },
+    });"#
+);
+
+test!(
+    Default::default(),
+    |_| as_folder(Visitor::default()),
+    test_this_alias_shadowed,
+    // Input codes
+    r#"export default {
+        data() {
+            return { loading: false };
+        },
+        methods: {
+            load(items) {
+                const { loading } = this;
+                function inner() {
+                    const loading = 1;
+                    return loading;
+                }
+                items.forEach((loading) => console.log(loading));
+                try {
+                    inner();
+                } catch (loading) {
+                    report(loading);
+                }
+                for (const loading of items) {
+                    report(loading);
+                }
+                return { loading };
+            },
+        },
+    };"#,
+    // Output codes after transformed with plugin
+    r#"import { defineComponent, ref } from '@vue/composition-api';
+    export default defineComponent({
+        setup (props, ctx) {
+            const loading = ref(false);
+
+            function load(items) {
+                function inner() {
+                    const loading = 1;
+                    return loading;
+                }
+                items.forEach((loading) => console.log(loading));
+                try {
+                    inner();
+                } catch (loading) {
+                    report(loading);
+                }
+                for (const loading of items) {
+                    report(loading);
+                }
+                return { loading: loading.value };
+            }
+
+            return {
+                loading,
+                load,
+            }
+        },
+    });"#
+);
+
+#[test]
+fn test_escaped_alias() {
+    let source = r#"export default {
+        methods: {
+            register() {
+                const vm = this;
+                registry.add(vm);
+            },
+        },
+    };"#;
+
+    let conversion = convert(source, &Options::default()).unwrap();
+    assert!(conversion
+        .output
+        .contains("const instance = getCurrentInstance().proxy;"));
+    assert!(conversion.output.contains("registry.add(instance);"));
+    assert!(!conversion.output.contains("vm"));
+    assert_eq!(conversion.diagnostics.len(), 1);
+    assert_eq!(conversion.diagnostics[0].code, "escaped-this-alias");
+}
+
+#[test]
+fn test_escaped_declared_alias() {
+    let source = r#"export default {
+        methods: {
+            register() {
+                const self = this;
+                registry.add(self);
+                return { self };
+            },
+        },
+    };"#;
+
+    let conversion = convert(source, &Options::default()).unwrap();
+    assert!(!conversion.output.contains("const self"));
+    assert!(conversion.output.contains("registry.add(instance);"));
+    assert!(conversion.output.contains("self: instance"));
+    assert_eq!(conversion.diagnostics.len(), 2);
+    assert!(conversion
+        .diagnostics
+        .iter()
+        .all(|diagnostic| diagnostic.code == "escaped-this-alias"));
+}
+
+#[test]
+fn test_unsupported_destructure() {
+    let source = r#"export default {
+        methods: {
+            copy() {
+                const { items = [], ...rest } = this;
+                return rest;
+            },
+        },
+    };"#;
+
+    let conversion = convert(source, &Options::default()).unwrap();
+    assert!(conversion.output.contains("= this;"));
+    assert_eq!(conversion.diagnostics.len(), 1);
+    assert_eq!(
+        conversion.diagnostics[0].code,
+        "unsupported-this-destructure"
+    );
+}
@@ -0,0 +1,125 @@
+use otc::{convert, Options};
+
+#[test]
+fn test_arrow_in_method() {
+    let source = r#"export default {
+        data() {
+            return { count: 0 };
+        },
+        methods: {
+            run() {
+                items.forEach(() => {
+                    this.count++;
+                });
+            },
+        },
+    };"#;
+
+    let conversion = convert(source, &Options::default()).unwrap();
+    assert!(conversion.output.contains("count.value++;"));
+    assert!(conversion.diagnostics.is_empty());
+}
+
+#[test]
+fn test_function_in_method() {
+    let source = r#"export default {
+        data() {
+            return { count: 0 };
+        },
+        methods: {
+            run() {
+                items.forEach(function () {
+                    this.count++;
+                });
+            },
+        },
+    };"#;
+
+    let conversion = convert(source, &Options::default()).unwrap();
+    assert!(conversion.output.contains("this.count++;"));
+    assert_eq!(conversion.diagnostics.len(), 1);
+    assert_eq!(conversion.diagnostics[0].code, "unbound-this");
+}
+
+#[test]
+fn test_object_getter_in_method() {
+    let source = r#"export default {
+        methods: {
+            wrap() {
+                return { get value() { return this.inner; } };
+            },
+        },
+    };"#;
+
+    let conversion = convert(source, &Options::default()).unwrap();
+    assert!(conversion.output.contains("return this.inner;"));
+    assert_eq!(conversion.diagnostics.len(), 1);
+    assert_eq!(conversion.diagnostics[0].code, "unbound-this");
+}
+
+#[test]
+fn test_outside_component() {
+    let source = r#"class Counter {
+        increment() {
+            this.count++;
+        }
+    }
+    function helper() {
+        return this.count;
+    }
+    export default {
+        data() {
+            return { count: 0 };
+        },
+    };"#;
+
+    let conversion = convert(source, &Options::default()).unwrap();
+    assert!(conversion.output.contains("this.count++;"));
+    assert!(conversion.output.contains("return this.count;"));
+    assert!(conversion.diagnostics.is_empty());
+}
+
+#[test]
+fn test_member_shadowed_by_param() {
+    let source = r#"export default {
+        data() {
+            return { items: [] };
+        },
+        methods: {
+            setItems(items) {
+                this.items = items;
+            },
+        },
+    };"#;
+
+    let conversion = convert(source, &Options::default()).unwrap();
+    assert_eq!(conversion.diagnostics.len(), 1);
+    assert_eq!(conversion.diagnostics[0].code, "shadowed-member");
+}
+
+#[test]
+fn test_member_shadowed_by_local() {
+    let source = r#"export default {
+        data() {
+            return { items: [] };
+        },
+        methods: {
+            clean() {
+                const items = this.items.filter(Boolean);
+                if (items.length) {
+                    const count = items.length;
+                    return count;
+                }
+            },
+            count() {
+                const { items } = this;
+                return items.length;
+            },
+        },
+    };"#;
+
+    let conversion = convert(source, &Options::default()).unwrap();
+    assert_eq!(conversion.diagnostics.len(), 1);
+    assert_eq!(conversion.diagnostics[0].code, "shadowed-member");
+    assert!(conversion.output.contains("return items.value.length;"));
+}
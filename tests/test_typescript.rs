@@ -0,0 +1,211 @@
+use std::path::Path;
+
+use otc::{convert, Options, Target};
+
+#[test]
+fn test_typescript_sfc() {
+    let source = r#"<script lang="ts">
+import Vue, { PropType } from 'vue';
+
+export default Vue.extend({
+    props: {
+        items: { type: Array as PropType<Item[]>, required: true },
+    },
+    data() {
+        return { selected: null as Item | null };
+    },
+    computed: {
+        count(): number {
+            return this.items.length;
+        },
+    },
+    methods: {
+        select(item: Item): void {
+            this.selected = item;
+        },
+    },
+});
+</script>
+"#;
+
+    let options = Options {
+        sfc: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        convert(source, &options).unwrap().output,
+        r#"<script lang="ts">
+import Vue, { PropType } from 'vue';
+import { defineComponent, ref, computed } from '@vue/composition-api';
+export default defineComponent({
+    props: {
+        items: {
+            type: Array as PropType<Item[]>,
+            required: true
+        }
+    },
+    setup (props, ctx) {
+        const selected = ref(null as Item | null);
+        const count = computed((): number =>props.items.length);
+        function select(item: Item): void {
+            selected.value = item;
+        }
+        return {
+            selected,
+            count,
+            select
+        };
+    }
+});
+</script>
+"#
+    );
+}
+
+#[test]
+fn test_typescript_script() {
+    let options = Options {
+        typescript: true,
+        ..Default::default()
+    };
+    let source = "export default { methods: { id<T>(value: T): T { return value; } } };";
+
+    assert!(convert(source, &options).unwrap().converted);
+    assert!(convert(source, &Options::default()).is_err());
+}
+
+#[test]
+fn test_tsx_sfc() {
+    let source = r#"<script lang="tsx">
+export default {
+    data() {
+        return { label: 'Save' as string };
+    },
+    render() {
+        return <button>{this.label}</button>;
+    },
+};
+</script>
+"#;
+
+    let options = Options {
+        sfc: true,
+        ..Default::default()
+    };
+    let output = convert(source, &options).unwrap().output;
+    assert!(output.contains("const label = ref('Save' as string);"));
+    assert!(output.contains("{this.label}</button>;"));
+}
+
+#[test]
+fn test_tsx_path() {
+    let options = Options::default().for_path(Path::new("src/Button.tsx"));
+    assert!(options.typescript && options.jsx);
+
+    let options = Options::default().for_path(Path::new("src/Button.jsx"));
+    assert!(!options.typescript && options.jsx);
+
+    let options = Options::default().for_path(Path::new("src/Button.vue"));
+    assert!(options.sfc && !options.typescript && !options.jsx);
+}
+
+#[test]
+fn test_define_component_wrapper() {
+    let source = r#"import { defineComponent } from '@vue/composition-api';
+export default defineComponent({
+    data() {
+        return { count: 0 };
+    },
+});
+"#;
+
+    assert_eq!(
+        convert(source, &Options::default()).unwrap().output,
+        r#"import { defineComponent, ref } from '@vue/composition-api';
+export default defineComponent({
+    setup (props, ctx) {
+        const count = ref(0);
+        return {
+            count
+        };
+    }
+});
+"#
+    );
+}
+
+fn typed_props() -> Options {
+    Options {
+        sfc: true,
+        target: Target::Vue3,
+        script_setup: true,
+        typed_props: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_typed_props() {
+    let source = r#"<script lang="ts">
+export default {
+    props: {
+        id: { type: String, required: true },
+        items: { type: Array as PropType<Item[]>, default: () => [] },
+        size: [String, Number],
+        options: {
+            type: Object,
+            default() {
+                return {};
+            },
+            validator: (value) => true,
+        },
+    },
+};
+</script>
+"#;
+
+    let conversion = convert(source, &typed_props()).unwrap();
+    assert_eq!(
+        conversion.output,
+        r#"<script setup lang="ts">
+const props = withDefaults(defineProps<{
+    id: string;
+    items?: Item[];
+    size?: string | number;
+    options?: Record<string, any>;
+}>(), {
+    items: ()=>[],
+    options () {
+        return {};
+    }
+});
+</script>
+"#
+    );
+    assert_eq!(conversion.diagnostics.len(), 1);
+    assert_eq!(conversion.diagnostics[0].code, "prop-validator-dropped");
+}
+
+#[test]
+fn test_typed_props_fallback() {
+    let source = r#"<script lang="ts">
+export default {
+    props: {
+        id: String,
+        value: { required: true },
+    },
+};
+</script>
+"#;
+
+    let conversion = convert(source, &typed_props()).unwrap();
+    assert!(conversion.output.contains("defineProps({"));
+    assert_eq!(conversion.diagnostics.len(), 1);
+    assert_eq!(conversion.diagnostics[0].code, "props-not-typed");
+
+    // Only TypeScript components get typed props
+    let source = source.replace(r#" lang="ts""#, "");
+    let conversion = convert(&source, &typed_props()).unwrap();
+    assert!(conversion.output.contains("defineProps({"));
+    assert!(conversion.diagnostics.is_empty());
+}
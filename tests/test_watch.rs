@@ -0,0 +1,164 @@
+use otc::visitor::Visitor;
+use otc::{convert, Options};
+use swc_core::testing_transform::test;
+use swc_ecma_visit::as_folder;
+
+test!(
+    Default::default(),
+    |_| as_folder(Visitor::default()),
+    test_watch_sources,
+    // Input codes
+    r#"export default {
+        props: { id: Number },
+        inject: ['theme'],
+        data() {
+            return { form: { name: '' }, count: 0 };
+        },
+        watch: {
+            id(value) {
+                console.log(value);
+            },
+            count() {},
+            theme() {},
+            'form.name'(name) {
+                console.log(name);
+            },
+            '$route.query.page': {
+                handler() {},
+                immediate: true,
+            },
+        },
+    };"#,
+    // Output codes after transformed with plugin
+    r#"import { defineComponent, inject, ref, watch } from '@vue/composition-api';
+    export default defineComponent({
+        props: { id: Number },
+        setup (props, ctx) {
+            const theme = inject('theme');
+            const form = ref({ name: '' });
+            const count = ref(0);
+            watch(() => props.id, (value) => {
+                console.log(value);
+            });
+            watch(count, () => {});
+            watch(() => theme, () => {});
+            watch(() => form.value.name, (name) => {
+                console.log(name);
+            });
+            watch(() => ctx.root.$route.query.page, () => {}, { immediate: true });
+
+            return {
+                theme,
+                form,
+                count,
+            }
+        },
+    });"#
+);
+
+#[test]
+fn test_unresolved_watch_source() {
+    let source = r#"export default {
+        watch: {
+            fromMixin() {},
+            'items[0]'() {},
+        },
+    };"#;
+
+    let conversion = convert(source, &Options::default()).unwrap();
+    assert!(conversion
+        .output
+        .contains("watch: {\n        fromMixin () {},"));
+    assert!(!conversion.output.contains("watch("));
+    assert_eq!(conversion.diagnostics.len(), 2);
+    assert!(conversion
+        .diagnostics
+        .iter()
+        .all(|diagnostic| diagnostic.code == "unresolved-watch-source"));
+}
+
+test!(
+    Default::default(),
+    |_| as_folder(Visitor::default()),
+    test_watch_handlers,
+    // Input codes
+    r#"export default {
+        data() {
+            return { count: 0, query: '' };
+        },
+        watch: {
+            count: 'onCountChanged',
+            query: [
+                'search',
+                { handler: 'track', immediate: true },
+                function (value) {
+                    this.count = value.length;
+                },
+                { handler: (value) => console.log(value), deep: true },
+            ],
+        },
+        methods: {
+            onCountChanged() {},
+            search() {},
+            track() {},
+        },
+    };"#,
+    // Output codes after transformed with plugin
+    r#"import { defineComponent, ref, watch } from '@vue/composition-api';
+    export default defineComponent({
+        setup (props, ctx) {
+            const count = ref(0);
+            const query = ref('');
+            watch(count, onCountChanged);
+            watch(query, search);
+            watch(query, track, { immediate: true });
+            watch(query, (value) => {
+                count.value = value.length;
+            });
+            watch(query, (value) => console.log(value), { deep: true });
+
+            function onCountChanged() {}
+            function search() {}
+            function track() {}
+
+            return {
+                count,
+                query,
+                onCountChanged,
+                search,
+                track,
+            }
+        },
+    });"#
+);
+
+#[test]
+fn test_unresolved_watch_handler() {
+    let source = r#"export default {
+        data() {
+            return { count: 0 };
+        },
+        watch: {
+            count: 'fromMixin',
+            other: {
+                handler() {
+                    this.count++;
+                },
+            },
+        },
+    };"#;
+
+    let conversion = convert(source, &Options::default()).unwrap();
+    assert!(conversion.output.contains("count: 'fromMixin'"));
+    assert!(conversion.output.contains("this.count++;"));
+    assert!(!conversion.output.contains("watch("));
+    let codes: Vec<&str> = conversion
+        .diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.code)
+        .collect();
+    assert_eq!(
+        codes,
+        vec!["unresolved-watch-handler", "unresolved-watch-source"]
+    );
+}